@@ -4,10 +4,10 @@ fn main() {
         Windows::Win32::System::WinRT::{
             CreateDispatcherQueueController, ICompositorDesktopInterop, RoInitialize,
         },
-        Windows::UI::Colors,
+        Windows::Win32::UI::PointerInput::GetPointerPenInfo,
         Windows::UI::Composition::Desktop::DesktopWindowTarget,
         Windows::UI::Composition::{
-            CompositionColorBrush, CompositionContainerShape, CompositionEllipseGeometry,
+            ColorKeyFrameAnimation, CompositionColorBrush, CompositionContainerShape, CompositionEllipseGeometry,
             CompositionNineGridBrush, CompositionScopedBatch, CompositionShapeCollection,
             CompositionSpriteShape, Compositor, ShapeVisual, SpriteVisual,
             Vector3KeyFrameAnimation, VisualCollection,
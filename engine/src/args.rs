@@ -0,0 +1,58 @@
+//! Command line handling shared by the binaries.
+
+use crate::config::{GameConfig, StartMode};
+use crate::difficulty::Difficulty;
+use crate::generation::Generation;
+use crate::tile::MarkCycle;
+use std::fmt;
+use std::str::FromStr;
+
+/// The value given after `name` on the command line, or `None` if `name` is
+/// not there. A missing or unreadable value is an error rather than being
+/// quietly replaced by the default.
+pub fn parse_arg<T: FromStr>(name: &str) -> Result<Option<T>, String>
+where
+    T::Err: fmt::Display,
+{
+    let mut args = std::env::args().skip_while(|arg| arg != name);
+    if args.next().is_none() {
+        return Ok(None);
+    }
+    let value = args
+        .next()
+        .ok_or_else(|| format!("{} needs a value", name))?;
+    value
+        .parse()
+        .map(Some)
+        .map_err(|error| format!("Invalid value '{}' for {}: {}", value, name, error))
+}
+
+/// Whether the flag `name` is on the command line.
+pub fn has_flag(name: &str) -> bool {
+    std::env::args().any(|arg| arg == name)
+}
+
+/// The game settings that every front-end takes: `--difficulty`, `--opening`,
+/// `--no-guess`, `--practice` and `--marks`.
+pub fn config_from_args() -> Result<GameConfig, String> {
+    let generation = if has_flag("--no-guess") {
+        Generation::no_guess()
+    } else {
+        Generation::Random
+    };
+    let start_mode = if has_flag("--opening") {
+        StartMode::Opening { radius: 1 }
+    } else {
+        StartMode::SingleTile
+    };
+    let mark_cycle = parse_arg::<MarkCycle>("--marks")?.unwrap_or_default();
+    let difficulty = parse_arg::<Difficulty>("--difficulty")?.unwrap_or_default();
+    let config = difficulty
+        .config()
+        .and_then(|config| config.with_start_mode(start_mode))
+        .map_err(|error| format!("Invalid --difficulty: {}", error))?;
+    Ok(config
+        .with_generation(generation)
+        .with_practice(has_flag("--practice"))
+        .with_mark_cycle(mark_cycle))
+}
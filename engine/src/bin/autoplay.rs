@@ -0,0 +1,78 @@
+//! Lets the reference bot play a batch of seeded games and reports how it did.
+//!
+//! ```text
+//! cargo run --release -p engine --bin autoplay -- --games 1000 --difficulty expert
+//! ```
+//!
+//! Games use the seeds `--seed`, `--seed + 1`, ... so that runs can be compared
+//! across generator and rule changes. `--no-guess`, `--opening` and the other
+//! game settings are read like the game reads them.
+
+use engine::args::{config_from_args, parse_arg};
+use engine::{play_game, Difficulty, Game, GameStatus, SolverBot};
+use std::time::Instant;
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let games: u64 = parse_arg("--games")?.unwrap_or(100);
+    let first_seed: u64 = parse_arg("--seed")?.unwrap_or(0);
+    let config = config_from_args()?;
+    let difficulty =
+        Difficulty::from_dimensions(config.width(), config.height(), config.num_mines());
+
+    let start = Instant::now();
+    let mut wins = 0;
+    let mut winning_moves = 0;
+    let mut forced_guesses = 0;
+    for seed in first_seed..(first_seed + games) {
+        let mut game = Game::with_seed(config, seed);
+        let report = play_game(&mut SolverBot, &mut game);
+        if report.status == GameStatus::Won {
+            wins += 1;
+            winning_moves += report.moves as u64;
+        }
+        forced_guesses += report.forced_guesses as u64;
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "Played {} {} games (seeds {}..{}) in {:.2?}",
+        games,
+        difficulty,
+        first_seed,
+        first_seed + games,
+        elapsed
+    );
+    println!(
+        "Win rate: {}/{} ({:.1}%)",
+        wins,
+        games,
+        percentage(wins, games)
+    );
+    if wins > 0 {
+        println!(
+            "Average moves per win: {:.1}",
+            winning_moves as f64 / wins as f64
+        );
+    }
+    println!(
+        "Forced guesses: {} ({:.2} per game)",
+        forced_guesses,
+        forced_guesses as f64 / games.max(1) as f64
+    );
+    Ok(())
+}
+
+fn percentage(count: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 * 100.0 / total as f64
+    }
+}
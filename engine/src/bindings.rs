@@ -0,0 +1,358 @@
+use crate::command::Command;
+use crate::difficulty::Difficulty;
+use crate::file_format::{read_header, write_header, FormatError, LineReader};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+const BINDINGS_HEADER: &str = "minesweeper-bindings";
+const BINDINGS_VERSION: u32 = 1;
+
+/// The order in which modifiers are written in front of a key name.
+const MODIFIERS: [&str; 3] = ["ctrl", "alt", "shift"];
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PointerButton {
+    Left,
+    Right,
+    Middle,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Gesture {
+    /// A touch that is lifted again quickly.
+    Tap,
+    /// A touch that is held for a while before it is lifted.
+    LongPress,
+}
+
+/// A button, key or gesture that can be bound to a command.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Input {
+    /// A key, named in lowercase and preceded by the modifiers held with it,
+    /// such as `f2`, `space` or `ctrl+shift+z`. Build it with `Input::key`.
+    Key(String),
+    Mouse(PointerButton),
+    /// Pressing with the eraser end of a pen.
+    PenEraser,
+    Touch(Gesture),
+}
+
+impl Input {
+    /// The input for the key called `name` pressed with the given modifiers.
+    pub fn key(name: &str, ctrl: bool, alt: bool, shift: bool) -> Self {
+        let mut key = String::new();
+        for (modifier, is_held) in MODIFIERS.iter().zip([ctrl, alt, shift]) {
+            if is_held {
+                key.push_str(modifier);
+                key.push('+');
+            }
+        }
+        key.push_str(&name.to_lowercase());
+        Input::Key(key)
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Input::Key(key) => write!(f, "key {}", key),
+            Input::Mouse(PointerButton::Left) => write!(f, "mouse left"),
+            Input::Mouse(PointerButton::Right) => write!(f, "mouse right"),
+            Input::Mouse(PointerButton::Middle) => write!(f, "mouse middle"),
+            Input::PenEraser => write!(f, "pen eraser"),
+            Input::Touch(Gesture::Tap) => write!(f, "touch tap"),
+            Input::Touch(Gesture::LongPress) => write!(f, "touch long-press"),
+        }
+    }
+}
+
+/// Which command each input triggers. Front-ends turn what they receive into
+/// an `Input` and ask for its command, so that none of them decide on their
+/// own what a button does.
+///
+/// The file format lists one binding per line after the header, as the input
+/// followed by the command or `none`:
+///
+/// ```text
+/// minesweeper-bindings 1
+/// mouse left mark
+/// mouse right reveal
+/// key ctrl+z undo
+/// key 4 new-game 24x20:80
+/// key h none
+/// ```
+///
+/// A file only has to list what it changes; every other input keeps its
+/// default binding.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Bindings {
+    bindings: Vec<(Input, Command)>,
+}
+
+impl Bindings {
+    /// Bindings that do nothing at all.
+    pub fn empty() -> Self {
+        Self {
+            bindings: Vec::new(),
+        }
+    }
+
+    /// The command bound to `input`. A key pressed with shift falls back to
+    /// the key without it, since shift only turns letters into capitals.
+    pub fn command(&self, input: &Input) -> Option<Command> {
+        let command = self
+            .bindings
+            .iter()
+            .find(|(bound, _)| bound == input)
+            .map(|(_, command)| *command);
+        match input {
+            Input::Key(key) if command.is_none() && key.contains("shift+") => {
+                self.command(&Input::Key(key.replacen("shift+", "", 1)))
+            }
+            _ => command,
+        }
+    }
+
+    /// Binds `input` to `command`, or unbinds it for `None`, replacing what
+    /// it was bound to before.
+    pub fn bind(&mut self, input: Input, command: Option<Command>) {
+        self.bindings.retain(|(bound, _)| *bound != input);
+        if let Some(command) = command {
+            self.bindings.push((input, command));
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(Input, Command)> {
+        self.bindings.iter()
+    }
+
+    /// Writes every binding. Since `load` starts from the defaults, the
+    /// default inputs that are no longer bound are written as `none`.
+    pub fn save<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_header(writer, BINDINGS_HEADER, BINDINGS_VERSION)?;
+        for (input, command) in &self.bindings {
+            writeln!(writer, "{} {}", input, command)?;
+        }
+        for (input, _) in &Self::default().bindings {
+            if !self.bindings.iter().any(|(bound, _)| bound == input) {
+                writeln!(writer, "{} none", input)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads bindings on top of the defaults.
+    pub fn load<R: BufRead>(reader: R) -> Result<Self, FormatError> {
+        let mut reader = LineReader::new(reader);
+        read_header(&mut reader, BINDINGS_HEADER, BINDINGS_VERSION)?;
+
+        let mut bindings = Self::default();
+        while let Some(fields) = reader.next_fields()? {
+            if fields.len() < 3 {
+                return Err(reader.invalid("expected an input and a command".to_string()));
+            }
+            let input = match (fields[0].as_str(), fields[1].as_str()) {
+                ("key", key) => parse_key(key)
+                    .ok_or_else(|| reader.invalid(format!("invalid key '{}'", key)))?,
+                ("mouse", "left") => Input::Mouse(PointerButton::Left),
+                ("mouse", "right") => Input::Mouse(PointerButton::Right),
+                ("mouse", "middle") => Input::Mouse(PointerButton::Middle),
+                ("pen", "eraser") => Input::PenEraser,
+                ("touch", "tap") => Input::Touch(Gesture::Tap),
+                ("touch", "long-press") => Input::Touch(Gesture::LongPress),
+                (kind, name) => {
+                    return Err(reader.invalid(format!("unknown input '{} {}'", kind, name)))
+                }
+            };
+
+            let command = fields[2..].join(" ");
+            let command = match command.as_str() {
+                "none" => None,
+                _ => {
+                    let command = Command::from_str(&command)
+                        .map_err(|error| reader.invalid(error.to_string()))?;
+                    if let Command::NewGame(difficulty) = command {
+                        difficulty.config()?;
+                    }
+                    Some(command)
+                }
+            };
+            bindings.bind(input, command);
+        }
+
+        Ok(bindings)
+    }
+
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.save(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, FormatError> {
+        Self::load(BufReader::new(File::open(path)?))
+    }
+}
+
+impl Default for Bindings {
+    /// The left button reveals, the right button and the eraser of a pen mark
+    /// and the middle button chords. The keyboard can do all of it as well:
+    /// the arrows or WASD move the selection, space or enter reveals, F marks
+    /// and C chords. Q and escape quit.
+    fn default() -> Self {
+        let mut bindings = Self::empty();
+        let mut bind = |input, command| bindings.bind(input, Some(command));
+        bind(Input::Mouse(PointerButton::Left), Command::Reveal);
+        bind(Input::Mouse(PointerButton::Right), Command::Mark);
+        bind(Input::Mouse(PointerButton::Middle), Command::Chord);
+        bind(Input::PenEraser, Command::Mark);
+        bind(Input::Touch(Gesture::Tap), Command::Reveal);
+        bind(Input::Touch(Gesture::LongPress), Command::Mark);
+
+        let keys = [
+            ("arrowleft", Command::MoveLeft),
+            ("arrowright", Command::MoveRight),
+            ("arrowup", Command::MoveUp),
+            ("arrowdown", Command::MoveDown),
+            ("a", Command::MoveLeft),
+            ("d", Command::MoveRight),
+            ("w", Command::MoveUp),
+            ("s", Command::MoveDown),
+            ("space", Command::Reveal),
+            ("enter", Command::Reveal),
+            ("f", Command::Mark),
+            ("c", Command::Chord),
+            ("f2", Command::Restart),
+            ("r", Command::Restart),
+            ("1", Command::NewGame(Difficulty::Beginner)),
+            ("2", Command::NewGame(Difficulty::Intermediate)),
+            ("3", Command::NewGame(Difficulty::Expert)),
+            ("ctrl+z", Command::Undo),
+            ("ctrl+shift+z", Command::Redo),
+            ("ctrl+y", Command::Redo),
+            ("p", Command::ToggleProbabilities),
+            ("h", Command::Hint),
+            ("q", Command::Quit),
+            ("escape", Command::Quit),
+        ];
+        for (key, command) in keys.iter() {
+            // The names above are all valid.
+            bind(parse_key(key).unwrap(), *command);
+        }
+        bindings
+    }
+}
+
+/// Parses a key name with optional modifiers in front, in any order and
+/// case, such as `Shift+Ctrl+Z`.
+fn parse_key(value: &str) -> Option<Input> {
+    let mut rest = value.to_lowercase();
+    let mut held = [false; 3];
+    'modifiers: loop {
+        for (index, modifier) in MODIFIERS.iter().enumerate() {
+            let prefix = format!("{}+", modifier);
+            // `ctrl++` is the plus key, not a modifier on its own.
+            if rest.len() > prefix.len() && rest.starts_with(&prefix) {
+                rest.replace_range(..prefix.len(), "");
+                held[index] = true;
+                continue 'modifiers;
+            }
+        }
+        break;
+    }
+    if rest.is_empty() {
+        return None;
+    }
+    Some(Input::key(&rest, held[0], held[1], held[2]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str) -> Input {
+        parse_key(name).unwrap()
+    }
+
+    fn round_trip(bindings: &Bindings) -> Bindings {
+        let mut file = Vec::new();
+        bindings.save(&mut file).unwrap();
+        Bindings::load(file.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn save_round_trips_changes_to_the_defaults() {
+        let mut bindings = Bindings::default();
+        bindings.bind(Input::Mouse(PointerButton::Left), Some(Command::Mark));
+        bindings.bind(key("ctrl+h"), Some(Command::Hint));
+        bindings.bind(key("h"), None);
+        bindings.bind(Input::Touch(Gesture::LongPress), None);
+        bindings.bind(Input::PenEraser, None);
+
+        let loaded = round_trip(&bindings);
+        assert_eq!(loaded.command(&key("h")), None);
+        assert_eq!(loaded.command(&Input::Touch(Gesture::LongPress)), None);
+        assert_eq!(loaded.command(&Input::PenEraser), None);
+        assert_eq!(loaded.command(&key("ctrl+h")), Some(Command::Hint));
+        assert_eq!(
+            loaded.command(&Input::Mouse(PointerButton::Left)),
+            Some(Command::Mark)
+        );
+        for (input, command) in bindings.iter() {
+            assert_eq!(loaded.command(input), Some(*command));
+        }
+        assert_eq!(loaded.iter().count(), bindings.iter().count());
+    }
+
+    #[test]
+    fn empty_bindings_stay_empty() {
+        assert_eq!(round_trip(&Bindings::empty()).iter().count(), 0);
+    }
+
+    #[test]
+    fn load_layers_the_file_over_the_defaults() {
+        let file = "minesweeper-bindings 1\nmouse left mark\npen eraser reveal\nkey 4 new-game 24x20:80\nkey h none\n";
+        let bindings = Bindings::load(file.as_bytes()).unwrap();
+        assert_eq!(
+            bindings.command(&Input::Mouse(PointerButton::Left)),
+            Some(Command::Mark)
+        );
+        assert_eq!(
+            bindings.command(&key("4")),
+            Some(Command::NewGame("24x20:80".parse().unwrap()))
+        );
+        assert_eq!(bindings.command(&Input::PenEraser), Some(Command::Reveal));
+        assert_eq!(bindings.command(&key("h")), None);
+        assert_eq!(bindings.command(&key("f2")), Some(Command::Restart));
+    }
+
+    #[test]
+    fn keys_ignore_the_order_of_modifiers_and_fall_back_without_shift() {
+        let bindings = Bindings::default();
+        assert_eq!(key("Shift+Ctrl+Z"), key("ctrl+shift+z"));
+        assert_eq!(bindings.command(&key("ctrl+shift+z")), Some(Command::Redo));
+        assert_eq!(bindings.command(&key("shift+f")), Some(Command::Mark));
+        assert_eq!(bindings.command(&key("ctrl+f")), None);
+        assert_eq!(bindings.command(&key("shift+q")), Some(Command::Quit));
+        assert_eq!(key("ctrl++"), Input::key("+", true, false, false));
+    }
+
+    #[test]
+    fn load_rejects_invalid_lines() {
+        for file in [
+            "minesweeper-bindings 1\nmouse left\n",
+            "minesweeper-bindings 1\nmouse back reveal\n",
+            "minesweeper-bindings 1\nkey h fly\n",
+            "minesweeper-bindings 1\nkey 4 new-game 2x2:9\n",
+        ] {
+            assert!(Bindings::load(file.as_bytes()).is_err(), "{}", file);
+        }
+        assert!(matches!(
+            Bindings::load("minesweeper-bindings 2\n".as_bytes()),
+            Err(FormatError::UnsupportedVersion(2))
+        ));
+    }
+}
@@ -0,0 +1,207 @@
+use crate::game::{Game, GameStatus};
+use crate::probability::mine_probabilities;
+use crate::solver::{deduce, Reason, Verdict, VisibleBoard, VisibleTile};
+use crate::tile::{MineState, TileCoordinate};
+
+/// A move a bot can make, mirroring the public `Game` API.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BotMove {
+    /// Reveals a tile the bot knows to be safe.
+    Reveal(TileCoordinate),
+    /// Reveals a tile that the bot could not prove safe.
+    Guess(TileCoordinate),
+    /// Flags a covered tile that is not marked yet.
+    Flag(TileCoordinate),
+    Chord(TileCoordinate),
+}
+
+/// Something that can play a game without a UI.
+pub trait Bot {
+    /// Picks the next move. `board` is all the bot gets to see; the bot's
+    /// own flags show up as `VisibleTile::Mine`. Returning `None` gives up.
+    fn next_move(&mut self, board: &VisibleBoard) -> Option<BotMove>;
+}
+
+/// Plays safe moves from the solver first, flags the mines it finds, and
+/// otherwise reveals the covered tile that is least likely to be a mine. On
+/// a board whose odds can only be approximated, that is the tile with the
+/// lowest approximation, which need not be the safest.
+/// Safe tiles found by a single number are cleared by chording that number.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct SolverBot;
+
+impl Bot for SolverBot {
+    fn next_move(&mut self, board: &VisibleBoard) -> Option<BotMove> {
+        let index_helper = board.index_helper();
+        let tile = |index: usize| TileCoordinate {
+            x: index_helper.compute_x_from_index(index),
+            y: index_helper.compute_y_from_index(index),
+        };
+
+        if board.covered_count() == board.tiles().len() {
+            // Nothing is known yet, and the middle is the most likely place
+            // for the first click to open an area.
+            return Some(BotMove::Guess(TileCoordinate {
+                x: index_helper.width() / 2,
+                y: index_helper.height() / 2,
+            }));
+        }
+
+        let deductions = deduce(board);
+        if let Some(deduction) = deductions
+            .iter()
+            .find(|deduction| deduction.verdict == Verdict::Safe)
+        {
+            // The bot's flags are known mines, so a number that the
+            // single-cell rule cleared has exactly as many flags as it shows.
+            return Some(match deduction.reason {
+                Reason::SingleCell(clue) => BotMove::Chord(clue.tile),
+                _ => BotMove::Reveal(deduction.tile),
+            });
+        }
+        if let Some(deduction) = deductions.first() {
+            return Some(BotMove::Flag(deduction.tile));
+        }
+
+        let probabilities = mine_probabilities(board)?.values;
+        (0..probabilities.len())
+            .filter(|index| board.tile(*index) == VisibleTile::Covered)
+            .min_by(|a, b| probabilities[*a].partial_cmp(&probabilities[*b]).unwrap())
+            .map(|index| BotMove::Guess(tile(index)))
+    }
+}
+
+/// How a game played by a bot went.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BotReport {
+    pub status: GameStatus,
+    /// The number of moves that changed the board.
+    pub moves: u32,
+    /// The number of `BotMove::Guess` moves, not counting the first click,
+    /// which is always safe.
+    pub forced_guesses: u32,
+}
+
+/// Lets `bot` play `game` until the game is over, the bot gives up, or one
+/// of its moves does not change the board.
+pub fn play_game<B: Bot>(bot: &mut B, game: &mut Game) -> BotReport {
+    let mut moves = 0;
+    let mut forced_guesses = 0;
+    while !game.is_game_over() {
+        let board = VisibleBoard::from_game(game, true);
+        let bot_move = match bot.next_move(&board) {
+            Some(bot_move) => bot_move,
+            None => break,
+        };
+
+        let result = match bot_move {
+            BotMove::Reveal(tile) => game.reveal(tile.x, tile.y),
+            BotMove::Guess(tile) => {
+                if game.are_mines_generated() {
+                    forced_guesses += 1;
+                }
+                game.reveal(tile.x, tile.y)
+            }
+            BotMove::Flag(tile) => {
+                if game.mine_state(tile.x, tile.y) != MineState::Empty {
+                    break;
+                }
+                // The mark cycle may put a question mark before the flag.
+                let mut result = game.cycle_mark(tile.x, tile.y);
+                while game.mine_state(tile.x, tile.y) == MineState::Question {
+                    result = game.cycle_mark(tile.x, tile.y);
+                }
+                result
+            }
+            BotMove::Chord(tile) => game.chord(tile.x, tile.y),
+        };
+        if result.changes.is_empty() {
+            break;
+        }
+        moves += 1;
+    }
+
+    BotReport {
+        status: game.status(),
+        moves,
+        forced_guesses,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difficulty::Difficulty;
+    use crate::notation::parse_board;
+    use crate::tile::MarkCycle;
+
+    fn tile(x: i32, y: i32) -> TileCoordinate {
+        TileCoordinate { x, y }
+    }
+
+    #[test]
+    fn solver_boards_are_won_without_guessing() {
+        let mut game = parse_board("*.*\n121").unwrap();
+        let report = play_game(&mut SolverBot, &mut game);
+        assert_eq!(
+            report,
+            BotReport {
+                status: GameStatus::Won,
+                moves: 2,
+                forced_guesses: 0,
+            }
+        );
+        // One flag is enough to chord the middle open.
+        assert_eq!(game.mine_state(2, 0), MineState::Flag);
+        assert_eq!(game.mine_state(1, 0), MineState::Revealed);
+    }
+
+    #[test]
+    fn untouched_boards_start_in_the_middle() {
+        let board = VisibleBoard::from_notation("...\n...\n.*.", true);
+        assert_eq!(
+            SolverBot.next_move(&board),
+            Some(BotMove::Guess(tile(1, 1)))
+        );
+    }
+
+    #[test]
+    fn losses_are_reported_with_their_moves() {
+        let mut game = parse_board("..\n.*").unwrap();
+        let report = play_game(&mut SolverBot, &mut game);
+        assert_eq!(
+            report,
+            BotReport {
+                status: GameStatus::Lost(tile(1, 1)),
+                moves: 1,
+                forced_guesses: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn seeded_games_are_played_to_the_end() {
+        let config = Difficulty::Beginner.config().unwrap();
+        for seed in 0..10 {
+            let mut game = Game::with_seed(config, seed);
+            let report = play_game(&mut SolverBot, &mut game);
+            assert!(game.is_game_over(), "seed {} was left unfinished", seed);
+            assert_eq!(report.status, game.status());
+            assert!(report.moves > 0);
+        }
+    }
+
+    #[test]
+    fn question_marks_first_do_not_stop_the_bot() {
+        let config = Difficulty::Beginner.config().unwrap();
+        for seed in 0..10 {
+            let mut game = Game::with_seed(config, seed);
+            let expected = play_game(&mut SolverBot, &mut game);
+
+            let config = config.with_mark_cycle(MarkCycle::QuestionFlag);
+            let mut game = Game::with_seed(config, seed);
+            assert_eq!(play_game(&mut SolverBot, &mut game), expected);
+            assert!(!game.mine_states().contains(&MineState::Question));
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::difficulty::Difficulty;
+use std::fmt;
+use std::str::FromStr;
+
+/// Something the player asks the game to do, whichever button, key or
+/// gesture it came from. `Minesweeper::execute` carries it out.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Command {
+    /// Reveals the selected tile, or chords the current tile if it is
+    /// revealed. Starts a new game once the game is over.
+    Reveal,
+    /// Cycles the mark on the selected tile.
+    Mark,
+    /// Reveals the covered neighbors of the current tile if all of its mines
+    /// are flagged.
+    Chord,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    Undo,
+    Redo,
+    Hint,
+    ToggleProbabilities,
+    /// Starts a new game with the same settings.
+    Restart,
+    /// Starts a new game at the given size, keeping the other settings.
+    NewGame(Difficulty),
+    /// Closes the front-end. The game itself has nothing to do for it.
+    Quit,
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Reveal => write!(f, "reveal"),
+            Command::Mark => write!(f, "mark"),
+            Command::Chord => write!(f, "chord"),
+            Command::MoveLeft => write!(f, "move-left"),
+            Command::MoveRight => write!(f, "move-right"),
+            Command::MoveUp => write!(f, "move-up"),
+            Command::MoveDown => write!(f, "move-down"),
+            Command::Undo => write!(f, "undo"),
+            Command::Redo => write!(f, "redo"),
+            Command::Hint => write!(f, "hint"),
+            Command::ToggleProbabilities => write!(f, "probabilities"),
+            Command::Restart => write!(f, "restart"),
+            Command::NewGame(difficulty) => write!(f, "new-game {}", difficulty),
+            Command::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseCommandError(String);
+
+impl fmt::Display for ParseCommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown command '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseCommandError {}
+
+impl FromStr for Command {
+    type Err = ParseCommandError;
+
+    /// Parses the names written by `Display`, such as `move-left` or
+    /// `new-game expert`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let error = || ParseCommandError(value.to_string());
+        let mut words = value.split_whitespace();
+        let command = match words.next().ok_or_else(error)? {
+            "reveal" => Command::Reveal,
+            "mark" => Command::Mark,
+            "chord" => Command::Chord,
+            "move-left" => Command::MoveLeft,
+            "move-right" => Command::MoveRight,
+            "move-up" => Command::MoveUp,
+            "move-down" => Command::MoveDown,
+            "undo" => Command::Undo,
+            "redo" => Command::Redo,
+            "hint" => Command::Hint,
+            "probabilities" => Command::ToggleProbabilities,
+            "restart" => Command::Restart,
+            "new-game" => {
+                let difficulty = words.next().ok_or_else(error)?;
+                Command::NewGame(difficulty.parse().map_err(|_| error())?)
+            }
+            "quit" => Command::Quit,
+            _ => return Err(error()),
+        };
+        match words.next() {
+            Some(_) => Err(error()),
+            None => Ok(command),
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use crate::difficulty::Difficulty;
+use crate::generation::Generation;
+use crate::tile::MarkCycle;
+use std::fmt;
+
+/// The largest width or height a board may have.
+pub const MAX_DIMENSION: i32 = 1024;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ConfigError {
+    /// The width or height is zero or negative.
+    InvalidDimensions { width: i32, height: i32 },
+    /// The width or height exceeds `MAX_DIMENSION`.
+    BoardTooLarge { width: i32, height: i32 },
+    /// The mine count is negative.
+    InvalidMineCount(i32),
+    /// There are not enough tiles to place every mine outside of the tiles
+    /// that are kept free for the first click.
+    TooManyMines { mines: i32, max_mines: i32 },
+    /// The radius of the opening around the first click is negative.
+    InvalidSafeRadius(i32),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::InvalidDimensions { width, height } => write!(
+                f,
+                "a {}x{} board is invalid, both dimensions must be at least 1",
+                width, height
+            ),
+            ConfigError::BoardTooLarge { width, height } => write!(
+                f,
+                "a {}x{} board is too large, neither dimension may exceed {}",
+                width, height, MAX_DIMENSION
+            ),
+            ConfigError::InvalidMineCount(mines) => {
+                write!(f, "{} is not a valid number of mines", mines)
+            }
+            ConfigError::TooManyMines { mines, max_mines } => write!(
+                f,
+                "{} mines do not fit on this board, at most {} can be placed",
+                mines, max_mines
+            ),
+            ConfigError::InvalidSafeRadius(radius) => write!(
+                f,
+                "{} is not a valid radius for the first click opening",
+                radius
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Which tiles around the first revealed tile are kept free of mines.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum StartMode {
+    /// Only the first revealed tile is safe. It may turn out to be a number.
+    #[default]
+    SingleTile,
+    /// Every tile within `radius` of the first revealed tile is safe, so the
+    /// first click always opens an area. A radius of 1 is the 3x3 neighborhood.
+    Opening { radius: i32 },
+}
+
+impl StartMode {
+    pub fn radius(&self) -> i32 {
+        match self {
+            StartMode::SingleTile => 0,
+            StartMode::Opening { radius } => *radius,
+        }
+    }
+}
+
+/// The dimensions and mine count of a game. A `GameConfig` can only be
+/// created through `GameConfig::new`, so every instance describes a board on
+/// which the mines can actually be placed.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct GameConfig {
+    width: i32,
+    height: i32,
+    num_mines: i32,
+    start_mode: StartMode,
+    generation: Generation,
+    practice: bool,
+    mark_cycle: MarkCycle,
+}
+
+impl GameConfig {
+    pub fn new(width: i32, height: i32, num_mines: i32) -> Result<Self, ConfigError> {
+        Self::with_dimensions(width, height, num_mines)?.validate_mine_count()
+    }
+
+    fn with_dimensions(width: i32, height: i32, num_mines: i32) -> Result<Self, ConfigError> {
+        if width < 1 || height < 1 {
+            return Err(ConfigError::InvalidDimensions { width, height });
+        }
+        if width > MAX_DIMENSION || height > MAX_DIMENSION {
+            return Err(ConfigError::BoardTooLarge { width, height });
+        }
+        if num_mines < 0 {
+            return Err(ConfigError::InvalidMineCount(num_mines));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            num_mines,
+            start_mode: StartMode::default(),
+            generation: Generation::default(),
+            practice: false,
+            mark_cycle: MarkCycle::default(),
+        })
+    }
+
+    fn validate_mine_count(self) -> Result<Self, ConfigError> {
+        let max_mines = self.max_mines();
+        if self.num_mines > max_mines {
+            return Err(ConfigError::TooManyMines {
+                mines: self.num_mines,
+                max_mines,
+            });
+        }
+
+        Ok(self)
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn num_mines(&self) -> i32 {
+        self.num_mines
+    }
+
+    pub fn start_mode(&self) -> StartMode {
+        self.start_mode
+    }
+
+    /// Changes how many tiles around the first click are kept free of mines.
+    /// Fails if the mines would no longer fit next to the larger safe zone.
+    pub fn with_start_mode(mut self, start_mode: StartMode) -> Result<Self, ConfigError> {
+        if start_mode.radius() < 0 {
+            return Err(ConfigError::InvalidSafeRadius(start_mode.radius()));
+        }
+
+        self.start_mode = start_mode;
+        self.validate_mine_count()
+    }
+
+    pub fn generation(&self) -> Generation {
+        self.generation
+    }
+
+    pub fn with_generation(mut self, generation: Generation) -> Self {
+        self.generation = generation;
+        self
+    }
+
+    pub fn is_practice(&self) -> bool {
+        self.practice
+    }
+
+    /// In practice mode, a move that hits a mine can be undone.
+    pub fn with_practice(mut self, practice: bool) -> Self {
+        self.practice = practice;
+        self
+    }
+
+    pub fn mark_cycle(&self) -> MarkCycle {
+        self.mark_cycle
+    }
+
+    /// Changes which marks a covered tile cycles through, and in which order.
+    pub fn with_mark_cycle(mut self, mark_cycle: MarkCycle) -> Self {
+        self.mark_cycle = mark_cycle;
+        self
+    }
+
+    pub fn tile_count(&self) -> i32 {
+        self.width * self.height
+    }
+
+    /// The number of tiles that never contain a mine because they are around
+    /// the first revealed tile. Clicks near an edge keep fewer tiles free, so
+    /// this is the size for a click in the middle of the board.
+    pub fn safe_zone_size(&self) -> i32 {
+        let diameter = self.start_mode.radius() * 2 + 1;
+        diameter.min(self.width) * diameter.min(self.height)
+    }
+
+    /// The highest mine count that still leaves room for the safe zone.
+    pub fn max_mines(&self) -> i32 {
+        self.tile_count() - self.safe_zone_size()
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        let difficulty = Difficulty::default();
+        Self {
+            width: difficulty.width(),
+            height: difficulty.height(),
+            num_mines: difficulty.mines(),
+            start_mode: StartMode::default(),
+            generation: Generation::default(),
+            practice: false,
+            mark_cycle: MarkCycle::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boards_need_a_tile() {
+        for (width, height) in [(0, 5), (5, 0), (-1, 5), (5, -3)] {
+            assert_eq!(
+                GameConfig::new(width, height, 0),
+                Err(ConfigError::InvalidDimensions { width, height })
+            );
+        }
+    }
+
+    #[test]
+    fn boards_are_at_most_max_dimension_wide_and_high() {
+        assert!(GameConfig::new(MAX_DIMENSION, MAX_DIMENSION, 1).is_ok());
+        for (width, height) in [(MAX_DIMENSION + 1, 5), (5, MAX_DIMENSION + 1)] {
+            assert_eq!(
+                GameConfig::new(width, height, 1),
+                Err(ConfigError::BoardTooLarge { width, height })
+            );
+        }
+    }
+
+    #[test]
+    fn mine_counts_are_not_negative() {
+        assert_eq!(
+            GameConfig::new(9, 9, -1),
+            Err(ConfigError::InvalidMineCount(-1))
+        );
+        assert!(GameConfig::new(9, 9, 0).is_ok());
+    }
+
+    #[test]
+    fn mines_leave_the_first_click_free() {
+        let config = GameConfig::new(9, 9, 80).unwrap();
+        assert_eq!(config.max_mines(), 80);
+        assert_eq!(
+            GameConfig::new(9, 9, 81),
+            Err(ConfigError::TooManyMines {
+                mines: 81,
+                max_mines: 80
+            })
+        );
+    }
+
+    #[test]
+    fn openings_leave_their_whole_area_free() {
+        let opening = StartMode::Opening { radius: 1 };
+        let config = GameConfig::new(9, 9, 72).unwrap();
+        assert_eq!(config.with_start_mode(opening).unwrap().max_mines(), 72);
+        assert_eq!(
+            GameConfig::new(9, 9, 73).unwrap().with_start_mode(opening),
+            Err(ConfigError::TooManyMines {
+                mines: 73,
+                max_mines: 72
+            })
+        );
+
+        // An opening larger than the board keeps every tile free.
+        let config = GameConfig::new(2, 2, 0).unwrap();
+        assert_eq!(config.with_start_mode(opening).unwrap().max_mines(), 0);
+        assert!(GameConfig::new(2, 2, 1)
+            .unwrap()
+            .with_start_mode(opening)
+            .is_err());
+    }
+
+    #[test]
+    fn opening_radii_are_not_negative() {
+        let config = GameConfig::new(9, 9, 10).unwrap();
+        assert_eq!(
+            config.with_start_mode(StartMode::Opening { radius: -1 }),
+            Err(ConfigError::InvalidSafeRadius(-1))
+        );
+        assert_eq!(
+            config
+                .with_start_mode(StartMode::Opening { radius: 0 })
+                .unwrap()
+                .max_mines(),
+            80
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use crate::config::{ConfigError, GameConfig};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum Difficulty {
+    /// 9x9 with 10 mines.
+    Beginner,
+    /// 16x16 with 40 mines.
+    #[default]
+    Intermediate,
+    /// 30x16 with 99 mines.
+    Expert,
+    Custom {
+        width: i32,
+        height: i32,
+        mines: i32,
+    },
+}
+
+impl Difficulty {
+    pub fn width(&self) -> i32 {
+        match self {
+            Difficulty::Beginner => 9,
+            Difficulty::Intermediate => 16,
+            Difficulty::Expert => 30,
+            Difficulty::Custom { width, .. } => *width,
+        }
+    }
+
+    pub fn height(&self) -> i32 {
+        match self {
+            Difficulty::Beginner => 9,
+            Difficulty::Intermediate => 16,
+            Difficulty::Expert => 16,
+            Difficulty::Custom { height, .. } => *height,
+        }
+    }
+
+    pub fn mines(&self) -> i32 {
+        match self {
+            Difficulty::Beginner => 10,
+            Difficulty::Intermediate => 40,
+            Difficulty::Expert => 99,
+            Difficulty::Custom { mines, .. } => *mines,
+        }
+    }
+
+    pub fn config(&self) -> Result<GameConfig, ConfigError> {
+        GameConfig::new(self.width(), self.height(), self.mines())
+    }
+
+    /// Returns the preset matching the given dimensions, or a custom
+    /// difficulty if there is none.
+    pub fn from_dimensions(width: i32, height: i32, mines: i32) -> Self {
+        [
+            Difficulty::Beginner,
+            Difficulty::Intermediate,
+            Difficulty::Expert,
+        ]
+        .iter()
+        .copied()
+        .find(|preset| {
+            preset.width() == width && preset.height() == height && preset.mines() == mines
+        })
+        .unwrap_or(Difficulty::Custom {
+            width,
+            height,
+            mines,
+        })
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difficulty::Beginner => write!(f, "beginner"),
+            Difficulty::Intermediate => write!(f, "intermediate"),
+            Difficulty::Expert => write!(f, "expert"),
+            Difficulty::Custom {
+                width,
+                height,
+                mines,
+            } => write!(f, "{}x{}:{}", width, height, mines),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseDifficultyError(String);
+
+impl fmt::Display for ParseDifficultyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid difficulty '{}', expected beginner, intermediate, expert or WIDTHxHEIGHT:MINES",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseDifficultyError {}
+
+impl FromStr for Difficulty {
+    type Err = ParseDifficultyError;
+
+    /// Parses a preset name or a custom size written as `WIDTHxHEIGHT:MINES`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let error = || ParseDifficultyError(value.to_string());
+        match value.to_ascii_lowercase().as_str() {
+            "beginner" => Ok(Difficulty::Beginner),
+            "intermediate" => Ok(Difficulty::Intermediate),
+            "expert" => Ok(Difficulty::Expert),
+            custom => {
+                let (size, mines) = custom.split_once(':').ok_or_else(error)?;
+                let (width, height) = size.split_once('x').ok_or_else(error)?;
+                Ok(Difficulty::Custom {
+                    width: width.trim().parse().map_err(|_| error())?,
+                    height: height.trim().parse().map_err(|_| error())?,
+                    mines: mines.trim().parse().map_err(|_| error())?,
+                })
+            }
+        }
+    }
+}
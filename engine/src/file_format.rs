@@ -0,0 +1,226 @@
+use crate::config::{ConfigError, GameConfig, StartMode};
+use crate::generation::Generation;
+use crate::tile::MarkCycle;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+/// Why a replay, saved game or board notation could not be read.
+#[derive(Debug)]
+pub enum FormatError {
+    Io(io::Error),
+    /// The file does not start with the header of the expected kind of file.
+    UnknownFormat,
+    /// The file was written by a newer or incompatible version of the game.
+    UnsupportedVersion(u32),
+    /// A line could not be parsed. Lines are numbered from 1.
+    InvalidLine {
+        line: usize,
+        message: String,
+    },
+    /// The file ended before all required data was read.
+    UnexpectedEnd,
+    /// The stored game configuration is not valid.
+    InvalidConfig(ConfigError),
+    /// Every line could be read, but together they do not describe a game
+    /// that could have been played.
+    InvalidState(String),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Io(error) => write!(f, "{}", error),
+            FormatError::UnknownFormat => write!(f, "the file is not in a known format"),
+            FormatError::UnsupportedVersion(version) => {
+                write!(f, "version {} of the file format is not supported", version)
+            }
+            FormatError::InvalidLine { line, message } => write!(f, "line {}: {}", line, message),
+            FormatError::UnexpectedEnd => write!(f, "the file ended unexpectedly"),
+            FormatError::InvalidConfig(error) => write!(f, "invalid game configuration: {}", error),
+            FormatError::InvalidState(message) => write!(f, "inconsistent board: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FormatError::Io(error) => Some(error),
+            FormatError::InvalidConfig(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for FormatError {
+    fn from(error: io::Error) -> Self {
+        FormatError::Io(error)
+    }
+}
+
+impl From<ConfigError> for FormatError {
+    fn from(error: ConfigError) -> Self {
+        FormatError::InvalidConfig(error)
+    }
+}
+
+/// Reads a line-based file where each line is a keyword followed by values
+/// separated by whitespace. Blank lines are skipped.
+pub(crate) struct LineReader<R: BufRead> {
+    reader: R,
+    line_number: usize,
+}
+
+impl<R: BufRead> LineReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line_number: 0,
+        }
+    }
+
+    /// Returns the fields of the next non-blank line, or `None` at the end of
+    /// the file.
+    pub fn next_fields(&mut self) -> Result<Option<Vec<String>>, FormatError> {
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            self.line_number += 1;
+
+            let fields: Vec<String> = line.split_whitespace().map(str::to_string).collect();
+            if !fields.is_empty() {
+                return Ok(Some(fields));
+            }
+        }
+    }
+
+    /// The number of the line that was read last, counting from 1.
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Reads the next line, which must start with `keyword`, and returns the
+    /// values after it.
+    pub fn expect(&mut self, keyword: &str) -> Result<Vec<String>, FormatError> {
+        let mut fields = self.next_fields()?.ok_or(FormatError::UnexpectedEnd)?;
+        if fields[0] != keyword {
+            return Err(self.invalid(format!("expected '{}', found '{}'", keyword, fields[0])));
+        }
+        fields.remove(0);
+        Ok(fields)
+    }
+
+    /// Reads a line made of `keyword` and exactly one value.
+    pub fn expect_value<T: FromStr>(&mut self, keyword: &str) -> Result<T, FormatError> {
+        let values = self.expect(keyword)?;
+        self.expect_count(&values, 1)?;
+        self.parse(&values[0])
+    }
+
+    pub fn expect_count(&self, values: &[String], count: usize) -> Result<(), FormatError> {
+        if values.len() != count {
+            return Err(self.invalid(format!("expected {} values, found {}", count, values.len())));
+        }
+        Ok(())
+    }
+
+    pub fn parse<T: FromStr>(&self, value: &str) -> Result<T, FormatError> {
+        value
+            .parse()
+            .map_err(|_| self.invalid(format!("'{}' is not a valid value", value)))
+    }
+
+    /// Builds an error that points at the line that was read last.
+    pub fn invalid(&self, message: String) -> FormatError {
+        FormatError::InvalidLine {
+            line: self.line_number,
+            message,
+        }
+    }
+}
+
+/// Writes the line that identifies the kind and version of a file.
+pub(crate) fn write_header<W: Write>(writer: &mut W, kind: &str, version: u32) -> io::Result<()> {
+    writeln!(writer, "{} {}", kind, version)
+}
+
+/// Checks the header written by `write_header`. Only `version` is accepted.
+pub(crate) fn read_header<R: BufRead>(
+    reader: &mut LineReader<R>,
+    kind: &str,
+    version: u32,
+) -> Result<(), FormatError> {
+    let fields = reader.next_fields()?.ok_or(FormatError::UnknownFormat)?;
+    if fields.len() != 2 || fields[0] != kind {
+        return Err(FormatError::UnknownFormat);
+    }
+    let file_version: u32 = fields[1].parse().map_err(|_| FormatError::UnknownFormat)?;
+    if file_version != version {
+        return Err(FormatError::UnsupportedVersion(file_version));
+    }
+    Ok(())
+}
+
+pub(crate) fn write_config<W: Write>(writer: &mut W, config: &GameConfig) -> io::Result<()> {
+    writeln!(
+        writer,
+        "size {} {} {}",
+        config.width(),
+        config.height(),
+        config.num_mines()
+    )?;
+    match config.start_mode() {
+        StartMode::SingleTile => writeln!(writer, "start-mode single")?,
+        StartMode::Opening { radius } => writeln!(writer, "start-mode opening {}", radius)?,
+    }
+    match config.generation() {
+        Generation::Random => writeln!(writer, "generation random")?,
+        Generation::NoGuess { max_attempts } => {
+            writeln!(writer, "generation no-guess {}", max_attempts)?
+        }
+    }
+    writeln!(writer, "practice {}", config.is_practice())?;
+    writeln!(writer, "marks {}", config.mark_cycle())
+}
+
+pub(crate) fn read_config<R: BufRead>(
+    reader: &mut LineReader<R>,
+) -> Result<GameConfig, FormatError> {
+    let size = reader.expect("size")?;
+    reader.expect_count(&size, 3)?;
+    let config = GameConfig::new(
+        reader.parse(&size[0])?,
+        reader.parse(&size[1])?,
+        reader.parse(&size[2])?,
+    )?;
+
+    let start_mode = reader.expect("start-mode")?;
+    let start_mode = match start_mode.first().map(String::as_str) {
+        Some("single") if start_mode.len() == 1 => StartMode::SingleTile,
+        Some("opening") if start_mode.len() == 2 => StartMode::Opening {
+            radius: reader.parse(&start_mode[1])?,
+        },
+        _ => return Err(reader.invalid("invalid start mode".to_string())),
+    };
+
+    let generation = reader.expect("generation")?;
+    let generation = match generation.first().map(String::as_str) {
+        Some("random") if generation.len() == 1 => Generation::Random,
+        Some("no-guess") if generation.len() == 2 => Generation::NoGuess {
+            max_attempts: reader.parse(&generation[1])?,
+        },
+        _ => return Err(reader.invalid("invalid generation".to_string())),
+    };
+
+    let practice = reader.expect_value("practice")?;
+    let mark_cycle: MarkCycle = reader.expect_value("marks")?;
+
+    Ok(config
+        .with_start_mode(start_mode)?
+        .with_generation(generation)
+        .with_practice(practice)
+        .with_mark_cycle(mark_cycle))
+}
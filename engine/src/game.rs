@@ -0,0 +1,1220 @@
+use crate::config::GameConfig;
+use crate::generation::{is_solvable_without_guessing, Generation, GenerationOutcome};
+use crate::hint::{find_hint, Hint};
+use crate::index_helper::IndexHelper;
+use crate::tile::{MineState, TileCoordinate};
+use rand::distributions::{Distribution, Uniform};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum MineGenerationState {
+    Deferred,
+    Generated,
+}
+
+/// Describes a single visible change to the board caused by a move.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TileChange {
+    /// A safe tile was revealed. Carries the number of neighboring mines.
+    Revealed(TileCoordinate, i32),
+    /// A tile containing a mine was revealed.
+    Mine(TileCoordinate),
+    /// The mark on a covered tile changed. Undo also uses this to cover a
+    /// tile again.
+    Marked(TileCoordinate, MineState),
+}
+
+impl TileChange {
+    pub fn tile_coordinate(&self) -> TileCoordinate {
+        match self {
+            TileChange::Revealed(tile_coordinate, _)
+            | TileChange::Mine(tile_coordinate)
+            | TileChange::Marked(tile_coordinate, _) => *tile_coordinate,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum GameStatus {
+    Playing,
+    Won,
+    Lost(TileCoordinate),
+}
+
+/// Everything a front-end needs to know to present the result of a move.
+#[derive(Clone, PartialEq, Debug)]
+pub struct MoveResult {
+    pub changes: Vec<TileChange>,
+    pub status: GameStatus,
+}
+
+impl MoveResult {
+    fn unchanged(status: GameStatus) -> Self {
+        Self {
+            changes: Vec::new(),
+            status,
+        }
+    }
+}
+
+/// The tiles changed by a single move, so that it can be undone and redone.
+struct HistoryEntry {
+    /// The index of each changed tile with its state before and after.
+    tiles: Vec<(usize, MineState, MineState)>,
+    status_before: GameStatus,
+    status_after: GameStatus,
+    /// The tile revealed by the move that placed the mines, if this is it.
+    first_reveal: Option<TileCoordinate>,
+}
+
+/// The parts of a game that a save file stores, checked by
+/// `Game::from_state` before they are trusted.
+pub(crate) struct GameState {
+    pub config: GameConfig,
+    pub seed: u64,
+    /// The mine layout and how it was generated, or `None` before the first
+    /// reveal.
+    pub mines: Option<(Vec<bool>, GenerationOutcome)>,
+    /// The stored numbers, which must match the mines. They are computed
+    /// from the mines when not given.
+    pub neighbor_counts: Option<Vec<i32>>,
+    pub mine_states: Vec<MineState>,
+    pub status: GameStatus,
+    pub hints_used: u32,
+    pub undos_used: u32,
+}
+
+pub struct Game {
+    config: GameConfig,
+    index_helper: IndexHelper,
+
+    mine_states: Vec<MineState>,
+    mines: Vec<bool>,
+    neighbor_counts: Vec<i32>,
+    mine_generation_state: MineGenerationState,
+    generation_outcome: Option<GenerationOutcome>,
+    seed: u64,
+
+    status: GameStatus,
+    hints_used: u32,
+
+    undo_stack: Vec<HistoryEntry>,
+    redo_stack: Vec<HistoryEntry>,
+    undos_used: u32,
+}
+
+impl Game {
+    /// Creates a game with a random seed.
+    pub fn new(config: GameConfig) -> Self {
+        Self::with_seed(config, rand::thread_rng().gen())
+    }
+
+    /// Creates a game whose mine layout is fully determined by the seed, the
+    /// config and the first revealed tile.
+    pub fn with_seed(config: GameConfig, seed: u64) -> Self {
+        let tile_count = config.tile_count() as usize;
+        Self {
+            config,
+            index_helper: IndexHelper::new(config.width(), config.height()),
+
+            mine_states: vec![MineState::Empty; tile_count],
+            mines: vec![false; tile_count],
+            neighbor_counts: Vec::new(),
+            mine_generation_state: MineGenerationState::Deferred,
+            generation_outcome: None,
+            seed,
+
+            status: GameStatus::Playing,
+            hints_used: 0,
+
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            undos_used: 0,
+        }
+    }
+
+    /// Rebuilds a game from saved state. The undo history is not part of it.
+    /// Returns a description of the problem if the parts do not describe a
+    /// game that could have been played.
+    pub(crate) fn from_state(state: GameState) -> Result<Self, String> {
+        let mut game = Self::with_seed(state.config, state.seed);
+        if state.mine_states.len() != game.mine_states.len() {
+            return Err("the number of tiles does not match the size".to_string());
+        }
+        game.mine_states = state.mine_states;
+        if game.mine_states.contains(&MineState::Question)
+            && !game.config.mark_cycle().allows(MineState::Question)
+        {
+            return Err("question marks are disabled".to_string());
+        }
+        game.status = state.status;
+        game.hints_used = state.hints_used;
+        game.undos_used = state.undos_used;
+
+        match state.mines {
+            Some((mines, outcome)) => {
+                if mines.len() != game.mines.len() {
+                    return Err("the number of tiles does not match the size".to_string());
+                }
+                if mines.iter().filter(|mine| **mine).count() != game.num_mines() as usize {
+                    return Err("the number of mines does not match the size".to_string());
+                }
+                game.mines = mines;
+                game.compute_neighbor_counts();
+                if let Some(neighbor_counts) = state.neighbor_counts {
+                    if game.neighbor_counts != neighbor_counts {
+                        return Err("the numbers do not match the mines".to_string());
+                    }
+                }
+                game.mine_generation_state = MineGenerationState::Generated;
+                game.generation_outcome = Some(outcome);
+            }
+            None => {
+                if game.mine_states.contains(&MineState::Revealed) {
+                    return Err("tiles are revealed before the mines were placed".to_string());
+                }
+                if state.neighbor_counts.is_some() {
+                    return Err("numbers are given before the mines were placed".to_string());
+                }
+            }
+        }
+
+        let revealed_mines: Vec<usize> = (0..game.mines.len())
+            .filter(|index| game.mines[*index] && game.mine_states[*index] == MineState::Revealed)
+            .collect();
+        let is_consistent = match game.status {
+            GameStatus::Playing => revealed_mines.is_empty() && !game.check_if_won(),
+            GameStatus::Won => game.are_mines_generated() && game.check_if_won(),
+            GameStatus::Lost(tile) => {
+                game.index_helper.is_in_bounds(tile.x, tile.y)
+                    && revealed_mines == [game.index_helper.compute_index(tile.x, tile.y)]
+            }
+        };
+        if !is_consistent {
+            return Err("the status does not match the board".to_string());
+        }
+
+        Ok(game)
+    }
+
+    pub fn config(&self) -> GameConfig {
+        self.config
+    }
+
+    pub fn width(&self) -> i32 {
+        self.config.width()
+    }
+
+    pub fn height(&self) -> i32 {
+        self.config.height()
+    }
+
+    pub fn num_mines(&self) -> i32 {
+        self.config.num_mines()
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn index_helper(&self) -> &IndexHelper {
+        &self.index_helper
+    }
+
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.status != GameStatus::Playing
+    }
+
+    pub fn are_mines_generated(&self) -> bool {
+        self.mine_generation_state == MineGenerationState::Generated
+    }
+
+    /// Describes how the mines were generated, or `None` before the first
+    /// tile has been revealed.
+    pub fn generation_outcome(&self) -> Option<GenerationOutcome> {
+        self.generation_outcome
+    }
+
+    pub fn mine_state(&self, x: i32, y: i32) -> MineState {
+        self.mine_states[self.index_helper.compute_index(x, y)]
+    }
+
+    pub fn mine_states(&self) -> &[MineState] {
+        &self.mine_states
+    }
+
+    /// Returns whether a mine is at the given index. Always false before the
+    /// first tile has been revealed.
+    pub fn is_mine(&self, index: usize) -> bool {
+        self.mines[index]
+    }
+
+    /// Returns the number of mines around the tile at the given index, or -1
+    /// if the tile is a mine itself. Only meaningful once the mines have been
+    /// generated.
+    pub fn neighbor_count(&self, index: usize) -> i32 {
+        self.neighbor_counts[index]
+    }
+
+    /// The number of hints given in this game. A game with no hints was
+    /// played without assistance.
+    pub fn hints_used(&self) -> u32 {
+        self.hints_used
+    }
+
+    /// Suggests a move using only the visible board, and counts it towards
+    /// `hints_used`. Returns `None` once the game is over.
+    pub fn hint(&mut self) -> Option<Hint> {
+        let hint = find_hint(self);
+        if hint.is_some() {
+            self.hints_used += 1;
+        }
+        hint
+    }
+
+    /// Reveals a covered, unmarked tile. The first reveal of a game generates
+    /// the mines, avoiding the safe zone around the revealed tile.
+    pub fn reveal(&mut self, x: i32, y: i32) -> MoveResult {
+        if self.is_game_over() || !self.is_in_bounds_and_unmarked(x, y) {
+            return MoveResult::unchanged(self.status);
+        }
+
+        let status_before = self.status;
+        let first_reveal = if self.are_mines_generated() {
+            None
+        } else {
+            Some(TileCoordinate { x, y })
+        };
+        let mut changes = Vec::new();
+        if self.sweep(x, y, &mut changes) {
+            self.status = GameStatus::Lost(TileCoordinate { x, y });
+        } else if self.check_if_won() {
+            self.status = GameStatus::Won;
+        }
+        self.record(&changes, MineState::Empty, status_before, first_reveal);
+
+        MoveResult {
+            changes,
+            status: self.status,
+        }
+    }
+
+    /// Moves a covered tile to the next mark in the cycle of the config.
+    pub fn cycle_mark(&mut self, x: i32, y: i32) -> MoveResult {
+        if self.is_game_over() || !self.index_helper.is_in_bounds(x, y) {
+            return MoveResult::unchanged(self.status);
+        }
+
+        let index = self.index_helper.compute_index(x, y);
+        if self.mine_states[index] == MineState::Revealed {
+            return MoveResult::unchanged(self.status);
+        }
+
+        let state_before = self.mine_states[index];
+        let state = self.config.mark_cycle().next(state_before);
+        self.mine_states[index] = state;
+        let changes = vec![TileChange::Marked(TileCoordinate { x, y }, state)];
+        self.record(&changes, state_before, self.status, None);
+
+        MoveResult {
+            changes,
+            status: self.status,
+        }
+    }
+
+    /// Reveals every unmarked neighbor of a revealed number once the number of
+    /// flags around it matches its count.
+    pub fn chord(&mut self, x: i32, y: i32) -> MoveResult {
+        if self.is_game_over() || !self.index_helper.is_in_bounds(x, y) {
+            return MoveResult::unchanged(self.status);
+        }
+
+        // Does the current tile have a number in it?
+        let index = self.index_helper.compute_index(x, y);
+        if self.mine_states[index] != MineState::Revealed || self.neighbor_counts[index] < 1 {
+            // No neighbors, or not revealed, do nothing!
+            return MoveResult::unchanged(self.status);
+        }
+
+        let neighbors = self.neighbors(x, y);
+
+        // See if all mines are marked that are in those 8 (or fewer) tiles
+        let flag_count = neighbors
+            .iter()
+            .filter(|tile| self.mine_state(tile.x, tile.y) == MineState::Flag)
+            .count() as i32;
+        if flag_count != self.neighbor_counts[index] {
+            // Too many or not enough flags
+            return MoveResult::unchanged(self.status);
+        }
+
+        // OK, go through the neighbors and try and reveal all of them with sweep if they're not flagged
+        let status_before = self.status;
+        let mut changes = Vec::new();
+        let mut hit_coordinate: Option<TileCoordinate> = None;
+        for neighbor in &neighbors {
+            // Is it unrevealed?  Only click on those spaces
+            if self.mine_state(neighbor.x, neighbor.y) != MineState::Empty {
+                // Already revealed, so don't click
+                continue;
+            }
+            if self.sweep(neighbor.x, neighbor.y, &mut changes) {
+                hit_coordinate = Some(*neighbor);
+                break;
+            }
+        }
+
+        if let Some(hit_coordinate) = hit_coordinate {
+            self.status = GameStatus::Lost(hit_coordinate);
+        } else if self.check_if_won() {
+            self.status = GameStatus::Won;
+        }
+        self.record(&changes, MineState::Empty, status_before, None);
+
+        MoveResult {
+            changes,
+            status: self.status,
+        }
+    }
+
+    /// The number of moves that were taken back in this game.
+    pub fn undos_used(&self) -> u32 {
+        self.undos_used
+    }
+
+    /// Returns whether `undo` would do anything. A move that hit a mine can
+    /// only be undone in practice mode.
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+            && (self.config.is_practice() || !matches!(self.status, GameStatus::Lost(_)))
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Takes back the last move. Undoing the first reveal also takes back the
+    /// mines, so that the next reveal is as safe as a first click again. The
+    /// result describes every tile that has to be redrawn, including all
+    /// mines once a lost game is playable again.
+    pub fn undo(&mut self) -> Option<MoveResult> {
+        if !self.can_undo() {
+            return None;
+        }
+
+        let entry = self.undo_stack.pop().unwrap();
+        let result = self.restore(&entry, false);
+        self.redo_stack.push(entry);
+        self.undos_used += 1;
+        Some(result)
+    }
+
+    /// Plays the last undone move again. Making any other move forgets the
+    /// moves that could be redone. Redoing the first reveal places the same
+    /// mines as before, since they only depend on the seed and that tile.
+    pub fn redo(&mut self) -> Option<MoveResult> {
+        let entry = self.redo_stack.pop()?;
+        let result = self.restore(&entry, true);
+        self.undo_stack.push(entry);
+        Some(result)
+    }
+
+    /// Builds a queue that contains the indices of the mines in a spiral starting
+    /// from the given tile, along with the number of mines found in each ring.
+    pub fn mines_in_rings(&self, center_x: i32, center_y: i32) -> (VecDeque<usize>, VecDeque<i32>) {
+        self.tiles_in_rings_where(center_x, center_y, |index| self.mines[index])
+    }
+
+    /// Like `mines_in_rings`, but with every tile of the board, for the win
+    /// animation.
+    pub fn tiles_in_rings(&self, center_x: i32, center_y: i32) -> (VecDeque<usize>, VecDeque<i32>) {
+        self.tiles_in_rings_where(center_x, center_y, |_| true)
+    }
+
+    /// Walks the rings around the given tile and keeps the tiles for which
+    /// `include` holds. The center is always kept.
+    fn tiles_in_rings_where<F: Fn(usize) -> bool>(
+        &self,
+        center_x: i32,
+        center_y: i32,
+        include: F,
+    ) -> (VecDeque<usize>, VecDeque<i32>) {
+        let mut tile_indices: VecDeque<usize> = VecDeque::new();
+        let mut tiles_per_ring: VecDeque<i32> = VecDeque::new();
+        let mut visited_tiles: i32 = 0;
+        let mut ring_level: i32 = 0;
+        while visited_tiles < self.config.tile_count() {
+            if ring_level == 0 {
+                let center_index = self.index_helper.compute_index(center_x, center_y);
+                tile_indices.push_back(center_index);
+                tiles_per_ring.push_back(1);
+                visited_tiles += 1;
+            } else {
+                let mut current_tiles_in_ring = 0;
+
+                // Check the top side
+                for x in (center_x - ring_level)..=(center_x + ring_level) {
+                    let y = center_y - ring_level;
+                    self.check_tile_for_animation(
+                        x,
+                        y,
+                        &include,
+                        &mut tile_indices,
+                        &mut visited_tiles,
+                        &mut current_tiles_in_ring,
+                    );
+                }
+
+                // Check the right side
+                for y in (center_y - ring_level + 1)..=(center_y + ring_level) {
+                    let x = center_x + ring_level;
+                    self.check_tile_for_animation(
+                        x,
+                        y,
+                        &include,
+                        &mut tile_indices,
+                        &mut visited_tiles,
+                        &mut current_tiles_in_ring,
+                    );
+                }
+
+                // Check the bottom side
+                for x in (center_x - ring_level)..(center_x + ring_level) {
+                    let y = center_y + ring_level;
+                    self.check_tile_for_animation(
+                        x,
+                        y,
+                        &include,
+                        &mut tile_indices,
+                        &mut visited_tiles,
+                        &mut current_tiles_in_ring,
+                    );
+                }
+
+                // Check the left side
+                for y in (center_y - ring_level + 1)..(center_y + ring_level) {
+                    let x = center_x - ring_level;
+                    self.check_tile_for_animation(
+                        x,
+                        y,
+                        &include,
+                        &mut tile_indices,
+                        &mut visited_tiles,
+                        &mut current_tiles_in_ring,
+                    );
+                }
+
+                if current_tiles_in_ring > 0 {
+                    tiles_per_ring.push_back(current_tiles_in_ring);
+                }
+            }
+            ring_level += 1;
+        }
+
+        (tile_indices, tiles_per_ring)
+    }
+
+    /// Remembers a move so that it can be undone. Every tile in `changes` was
+    /// in `state_before` before the move; reveals only touch unmarked tiles.
+    /// `first_reveal` is the revealed tile if the move placed the mines.
+    fn record(
+        &mut self,
+        changes: &[TileChange],
+        state_before: MineState,
+        status_before: GameStatus,
+        first_reveal: Option<TileCoordinate>,
+    ) {
+        if changes.is_empty() {
+            return;
+        }
+
+        let tiles = changes
+            .iter()
+            .map(|change| {
+                let tile_coordinate = change.tile_coordinate();
+                let index = self
+                    .index_helper
+                    .compute_index(tile_coordinate.x, tile_coordinate.y);
+                (index, state_before, self.mine_states[index])
+            })
+            .collect();
+        self.undo_stack.push(HistoryEntry {
+            tiles,
+            status_before,
+            status_after: self.status,
+            first_reveal,
+        });
+        self.redo_stack.clear();
+    }
+
+    fn restore(&mut self, entry: &HistoryEntry, forward: bool) -> MoveResult {
+        if let (true, Some(tile)) = (forward, entry.first_reveal) {
+            self.generate_mines(tile.x, tile.y);
+            self.mine_generation_state = MineGenerationState::Generated;
+        }
+
+        let was_lost = matches!(self.status, GameStatus::Lost(_));
+        let mut changes = Vec::new();
+        for (index, before, after) in &entry.tiles {
+            self.mine_states[*index] = if forward { *after } else { *before };
+            changes.push(self.describe_tile(*index));
+        }
+
+        if let (false, Some(_)) = (forward, entry.first_reveal) {
+            // Every tile is covered again, so the mines can be forgotten.
+            self.mines.iter_mut().for_each(|mine| *mine = false);
+            self.neighbor_counts.clear();
+            self.mine_generation_state = MineGenerationState::Deferred;
+            self.generation_outcome = None;
+        }
+        self.status = if forward {
+            entry.status_after
+        } else {
+            entry.status_before
+        };
+
+        if was_lost && !matches!(self.status, GameStatus::Lost(_)) {
+            // The loss animation shows every mine, so they all need to be
+            // covered again.
+            for index in 0..self.mines.len() {
+                if self.mines[index] {
+                    changes.push(self.describe_tile(index));
+                }
+            }
+        }
+
+        MoveResult {
+            changes,
+            status: self.status,
+        }
+    }
+
+    /// Describes every tile that is not plainly covered, so that a front-end
+    /// can draw a restored game from scratch.
+    pub(crate) fn current_board(&self) -> MoveResult {
+        MoveResult {
+            changes: (0..self.mine_states.len())
+                .filter(|index| self.mine_states[*index] != MineState::Empty)
+                .map(|index| self.describe_tile(index))
+                .collect(),
+            status: self.status,
+        }
+    }
+
+    /// Describes how the tile at `index` looks in its current state.
+    fn describe_tile(&self, index: usize) -> TileChange {
+        let tile_coordinate = TileCoordinate {
+            x: self.index_helper.compute_x_from_index(index),
+            y: self.index_helper.compute_y_from_index(index),
+        };
+        match self.mine_states[index] {
+            MineState::Revealed if self.mines[index] => TileChange::Mine(tile_coordinate),
+            MineState::Revealed => {
+                TileChange::Revealed(tile_coordinate, self.neighbor_counts[index])
+            }
+            state => TileChange::Marked(tile_coordinate, state),
+        }
+    }
+
+    fn neighbors(&self, x: i32, y: i32) -> Vec<TileCoordinate> {
+        let base_vec: Vec<(i32, i32)> = vec![
+            (x - 1, y - 1),
+            (x, y - 1),
+            (x + 1, y - 1),
+            (x - 1, y),
+            (x + 1, y),
+            (x - 1, y + 1),
+            (x, y + 1),
+            (x + 1, y + 1),
+        ];
+
+        // Filter out-of-bounds if we're on the edges
+        base_vec
+            .into_iter()
+            .filter(|(x, y)| self.index_helper.is_in_bounds(*x, *y))
+            .map(|(x, y)| TileCoordinate { x, y })
+            .collect()
+    }
+
+    fn sweep(&mut self, x: i32, y: i32, changes: &mut Vec<TileChange>) -> bool {
+        if self.mine_generation_state == MineGenerationState::Deferred {
+            // We don't want the first thing that the user clicks to be a mine.
+            // Generate mines but avoid the safe zone around where the user clicked.
+            self.generate_mines(x, y);
+            self.mine_generation_state = MineGenerationState::Generated;
+        }
+
+        let mut hit_mine = false;
+        let mut sweeps: VecDeque<usize> = VecDeque::new();
+        sweeps.push_back(self.index_helper.compute_index(x, y));
+        self.reveal_index(*sweeps.front().unwrap(), changes);
+
+        while !sweeps.is_empty() {
+            let index = *sweeps.front().unwrap();
+            let current_x = self.index_helper.compute_x_from_index(index);
+            let current_y = self.index_helper.compute_y_from_index(index);
+
+            if self.mines[index] {
+                // We hit a mine, game over
+                hit_mine = true;
+                break;
+            }
+
+            if self.neighbor_counts[index] == 0 {
+                self.push_if_unmarked(&mut sweeps, current_x - 1, current_y - 1, changes);
+                self.push_if_unmarked(&mut sweeps, current_x, current_y - 1, changes);
+                self.push_if_unmarked(&mut sweeps, current_x + 1, current_y - 1, changes);
+                self.push_if_unmarked(&mut sweeps, current_x + 1, current_y, changes);
+                self.push_if_unmarked(&mut sweeps, current_x + 1, current_y + 1, changes);
+                self.push_if_unmarked(&mut sweeps, current_x, current_y + 1, changes);
+                self.push_if_unmarked(&mut sweeps, current_x - 1, current_y + 1, changes);
+                self.push_if_unmarked(&mut sweeps, current_x - 1, current_y, changes);
+            }
+
+            sweeps.pop_front().unwrap();
+        }
+
+        hit_mine
+    }
+
+    fn reveal_index(&mut self, index: usize, changes: &mut Vec<TileChange>) {
+        let tile_coordinate = TileCoordinate {
+            x: self.index_helper.compute_x_from_index(index),
+            y: self.index_helper.compute_y_from_index(index),
+        };
+
+        if self.mines[index] {
+            changes.push(TileChange::Mine(tile_coordinate));
+        } else {
+            let count = self.neighbor_counts[index];
+            changes.push(TileChange::Revealed(tile_coordinate, count));
+        }
+
+        self.mine_states[index] = MineState::Revealed;
+    }
+
+    fn is_in_bounds_and_unmarked(&self, x: i32, y: i32) -> bool {
+        let index = self.index_helper.compute_index(x, y);
+        self.index_helper.is_in_bounds(x, y) && self.mine_states[index] == MineState::Empty
+    }
+
+    fn push_if_unmarked(
+        &mut self,
+        sweeps: &mut VecDeque<usize>,
+        x: i32,
+        y: i32,
+        changes: &mut Vec<TileChange>,
+    ) {
+        if self.is_in_bounds_and_unmarked(x, y) {
+            let index = self.index_helper.compute_index(x, y);
+            self.reveal_index(index, changes);
+            sweeps.push_back(index);
+        }
+    }
+
+    fn generate_mines(&mut self, exclude_x: i32, exclude_y: i32) {
+        let start_index = self.index_helper.compute_index(exclude_x, exclude_y);
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+
+        let outcome = match self.config.generation() {
+            Generation::Random => {
+                self.place_mines(&mut rng, exclude_x, exclude_y);
+                GenerationOutcome::Random
+            }
+            Generation::NoGuess { max_attempts } => {
+                let max_attempts = max_attempts.max(1);
+                let mut outcome = GenerationOutcome::BudgetExhausted {
+                    attempts: max_attempts,
+                };
+                for attempt in 1..=max_attempts {
+                    self.place_mines(&mut rng, exclude_x, exclude_y);
+                    if is_solvable_without_guessing(
+                        &self.index_helper,
+                        &self.neighbor_counts,
+                        self.num_mines(),
+                        start_index,
+                    ) {
+                        outcome = GenerationOutcome::NoGuess { attempts: attempt };
+                        break;
+                    }
+                }
+                outcome
+            }
+        };
+        self.generation_outcome = Some(outcome);
+    }
+
+    fn place_mines(&mut self, rng: &mut ChaCha8Rng, exclude_x: i32, exclude_y: i32) {
+        for mine in self.mines.iter_mut() {
+            *mine = false;
+        }
+
+        // Sample u32 rather than usize so that a seed produces the same board on
+        // 32-bit and 64-bit targets.
+        let between = Uniform::from(0..self.config.tile_count() as u32);
+        for _i in 0..self.num_mines() {
+            let mut index: usize;
+            // do while loops look weird in rust...
+            while {
+                index = between.sample(rng) as usize;
+                self.is_in_safe_zone(index, exclude_x, exclude_y) || self.mines[index]
+            } {}
+
+            self.mines[index] = true;
+        }
+
+        self.compute_neighbor_counts();
+    }
+
+    fn compute_neighbor_counts(&mut self) {
+        self.neighbor_counts.clear();
+        for i in 0..self.mines.len() {
+            let x = self.index_helper.compute_x_from_index(i);
+            let y = self.index_helper.compute_y_from_index(i);
+
+            if self.mines[i] {
+                // -1 means a mine
+                self.neighbor_counts.push(-1);
+            } else {
+                let count = self.get_surrounding_mine_count(x, y);
+                self.neighbor_counts.push(count);
+            }
+        }
+    }
+
+    fn is_in_safe_zone(&self, index: usize, start_x: i32, start_y: i32) -> bool {
+        let radius = self.config.start_mode().radius();
+        let x = self.index_helper.compute_x_from_index(index);
+        let y = self.index_helper.compute_y_from_index(index);
+        (x - start_x).abs() <= radius && (y - start_y).abs() <= radius
+    }
+
+    fn test_spot(&self, x: i32, y: i32) -> bool {
+        self.index_helper.is_in_bounds(x, y) && self.mines[self.index_helper.compute_index(x, y)]
+    }
+
+    fn get_surrounding_mine_count(&self, x: i32, y: i32) -> i32 {
+        let mut count = 0;
+
+        if self.test_spot(x + 1, y) {
+            count += 1;
+        }
+
+        if self.test_spot(x - 1, y) {
+            count += 1;
+        }
+
+        if self.test_spot(x, y + 1) {
+            count += 1;
+        }
+
+        if self.test_spot(x, y - 1) {
+            count += 1;
+        }
+
+        if self.test_spot(x + 1, y + 1) {
+            count += 1;
+        }
+
+        if self.test_spot(x - 1, y - 1) {
+            count += 1;
+        }
+
+        if self.test_spot(x - 1, y + 1) {
+            count += 1;
+        }
+
+        if self.test_spot(x + 1, y - 1) {
+            count += 1;
+        }
+
+        count
+    }
+
+    fn check_tile_for_animation<F: Fn(usize) -> bool>(
+        &self,
+        x: i32,
+        y: i32,
+        include: &F,
+        tile_indices: &mut VecDeque<usize>,
+        visited_tiles: &mut i32,
+        tiles_in_ring: &mut i32,
+    ) {
+        if self.index_helper.is_in_bounds(x, y) {
+            let tile_index = self.index_helper.compute_index(x, y);
+            if include(tile_index) {
+                tile_indices.push_back(tile_index);
+                *tiles_in_ring += 1;
+            }
+            *visited_tiles += 1;
+        }
+    }
+
+    fn check_if_won(&self) -> bool {
+        self.mine_states
+            .iter()
+            .filter(|state| **state != MineState::Revealed)
+            .count()
+            == self.num_mines() as usize
+    }
+}
+
+/// Pairs every index from `Game::tiles_in_rings` or `Game::mines_in_rings`
+/// with the delay of its ring, for front-ends that animate one ring after
+/// the other. `tiles_per_ring` holds the number of consecutive indices in
+/// each ring, and the rings start `step` apart.
+pub fn ring_delays(
+    indices: VecDeque<usize>,
+    mut tiles_per_ring: VecDeque<i32>,
+    step: Duration,
+) -> Vec<(usize, Duration)> {
+    let mut delays = Vec::new();
+    let mut current_delay = Duration::from_millis(0);
+    let mut current_tiles_count = 0;
+    for index in indices {
+        delays.push((index, current_delay));
+        current_tiles_count += 1;
+
+        if Some(&current_tiles_count) == tiles_per_ring.front() {
+            current_tiles_count = 0;
+            tiles_per_ring.pop_front();
+            current_delay += step;
+        }
+    }
+    delays
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StartMode;
+    use crate::difficulty::Difficulty;
+    use crate::notation::{parse_board, print_board};
+    use crate::tile::MarkCycle;
+
+    fn board(text: &str) -> Game {
+        parse_board(text).unwrap()
+    }
+
+    fn text(game: &Game) -> String {
+        print_board(game).unwrap()
+    }
+
+    #[test]
+    fn reveal_floods_empty_tiles() {
+        let mut game = board(".....\n.....\n...*.");
+        let result = game.reveal(0, 0);
+        assert_eq!(text(&game), "00000\n00111\n001*.\n");
+        assert_eq!(result.changes.len(), 13);
+        assert_eq!(result.status, GameStatus::Playing);
+    }
+
+    #[test]
+    fn reveal_shows_a_number_without_flooding() {
+        let mut game = board("...\n.*.\n...");
+        let result = game.reveal(0, 0);
+        assert_eq!(
+            result.changes,
+            [TileChange::Revealed(TileCoordinate { x: 0, y: 0 }, 1)]
+        );
+        assert_eq!(text(&game), "1..\n.*.\n...\n");
+    }
+
+    #[test]
+    fn flood_stops_at_marks() {
+        let mut game = board("f..\n...\n..q");
+        game.reveal(1, 1);
+        assert_eq!(text(&game), "f00\n000\n00q\n");
+        assert_eq!(game.status(), GameStatus::Playing);
+    }
+
+    #[test]
+    fn marked_and_revealed_tiles_cannot_be_revealed() {
+        let mut game = board("F1.\n11.\n...");
+        assert!(game.reveal(0, 0).changes.is_empty());
+        assert!(game.reveal(1, 0).changes.is_empty());
+        assert_eq!(text(&game), "F1.\n11.\n...\n");
+    }
+
+    #[test]
+    fn marks_cycle_through_flag_and_question() {
+        let mut game = board("*.\n..");
+        let mut states = Vec::new();
+        for _ in 0..3 {
+            game.cycle_mark(0, 0);
+            states.push(game.mine_state(0, 0));
+        }
+        assert_eq!(
+            states,
+            [MineState::Flag, MineState::Question, MineState::Empty]
+        );
+    }
+
+    #[test]
+    fn marks_follow_the_mark_cycle() {
+        let config = GameConfig::new(2, 2, 1)
+            .unwrap()
+            .with_mark_cycle(MarkCycle::FlagOnly);
+        let mut game = Game::with_seed(config, 7);
+        game.cycle_mark(1, 1);
+        assert_eq!(game.mine_state(1, 1), MineState::Flag);
+        game.cycle_mark(1, 1);
+        assert_eq!(game.mine_state(1, 1), MineState::Empty);
+    }
+
+    #[test]
+    fn revealed_tiles_cannot_be_marked() {
+        let mut game = board("1.\n*.");
+        assert!(game.cycle_mark(0, 0).changes.is_empty());
+        assert_eq!(game.mine_state(0, 0), MineState::Revealed);
+    }
+
+    #[test]
+    fn revealing_every_safe_tile_wins() {
+        let mut game = board("*..\n...\n...");
+        assert_eq!(game.reveal(2, 2).status, GameStatus::Won);
+        assert_eq!(text(&game), "*10\n110\n000\n");
+        assert!(game.is_game_over());
+    }
+
+    #[test]
+    fn covered_mines_do_not_stop_a_win() {
+        for fixture in ["*1.\n11.\n...", "F1.\n11.\n...", "Q1.\n11.\n..."] {
+            let mut game = board(fixture);
+            assert_eq!(game.reveal(2, 2).status, GameStatus::Won);
+        }
+    }
+
+    #[test]
+    fn revealing_a_mine_loses() {
+        let mut game = board("1*\n..");
+        let result = game.reveal(1, 0);
+        let hit = TileCoordinate { x: 1, y: 0 };
+        assert_eq!(result.status, GameStatus::Lost(hit));
+        assert_eq!(result.changes, [TileChange::Mine(hit)]);
+        assert_eq!(text(&game), "1X\n..\n");
+
+        // Nothing can be played after the game is lost.
+        assert!(game.reveal(0, 1).changes.is_empty());
+        assert!(game.cycle_mark(0, 1).changes.is_empty());
+        assert_eq!(game.status(), GameStatus::Lost(hit));
+    }
+
+    #[test]
+    fn first_click_is_never_a_mine() {
+        let config = Difficulty::Expert.config().unwrap();
+        for seed in 0..200 {
+            let mut game = Game::with_seed(config, seed);
+            let (x, y) = ((seed % 30) as i32, (seed % 16) as i32);
+            assert!(!game.are_mines_generated());
+            assert_ne!(
+                game.reveal(x, y).status,
+                GameStatus::Lost(TileCoordinate { x, y })
+            );
+            let index = game.index_helper().compute_index(x, y);
+            assert!(!game.is_mine(index));
+            assert_eq!(game.mines.iter().filter(|mine| **mine).count(), 99);
+        }
+    }
+
+    #[test]
+    fn first_click_is_safe_on_a_crowded_board() {
+        for seed in 0..20 {
+            let mut game = Game::with_seed(GameConfig::new(3, 3, 8).unwrap(), seed);
+            assert_eq!(game.reveal(1, 1).status, GameStatus::Won);
+            assert_eq!(text(&game), "***\n*8*\n***\n");
+        }
+    }
+
+    fn layout(config: GameConfig, seed: u64, x: i32, y: i32) -> Vec<bool> {
+        let mut game = Game::with_seed(config, seed);
+        game.reveal(x, y);
+        game.mines
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_layout() {
+        let config = Difficulty::Intermediate.config().unwrap();
+        for seed in 0..20 {
+            assert_eq!(layout(config, seed, 3, 5), layout(config, seed, 3, 5));
+        }
+    }
+
+    #[test]
+    fn different_seeds_give_different_layouts() {
+        let config = Difficulty::Intermediate.config().unwrap();
+        let layouts: Vec<Vec<bool>> = (0..20).map(|seed| layout(config, seed, 3, 5)).collect();
+        for (index, first) in layouts.iter().enumerate() {
+            assert!(layouts[index + 1..].iter().all(|other| other != first));
+        }
+    }
+
+    #[test]
+    fn seeded_layout_is_stable() {
+        // Shared seeds must keep producing the same board, so a change to
+        // the generator or an upgrade of rand or rand_chacha that moves these
+        // mines breaks every seed that was ever shared.
+        let mut game = Game::with_seed(Difficulty::Beginner.config().unwrap(), 1234);
+        game.reveal(4, 4);
+        assert_eq!(
+            text(&game),
+            "........*\n\
+             .*.......\n\
+             .*....*..\n\
+             .*...*...\n\
+             *..*2....\n\
+             .........\n\
+             .........\n\
+             ..*......\n\
+             ........*\n"
+        );
+    }
+
+    #[test]
+    fn undo_and_redo_restore_the_board() {
+        let mut game = board("...\n.*.\n...");
+        game.reveal(0, 0);
+        game.cycle_mark(1, 1);
+        assert_eq!(text(&game), "1..\n.F.\n...\n");
+
+        let result = game.undo().unwrap();
+        assert_eq!(
+            result.changes,
+            [TileChange::Marked(
+                TileCoordinate { x: 1, y: 1 },
+                MineState::Empty
+            )]
+        );
+        game.undo().unwrap();
+        assert_eq!(text(&game), "...\n.*.\n...\n");
+        assert_eq!(game.undo(), None);
+        assert_eq!(game.undos_used(), 2);
+
+        game.redo().unwrap();
+        game.redo().unwrap();
+        assert_eq!(text(&game), "1..\n.F.\n...\n");
+        assert_eq!(game.redo(), None);
+    }
+
+    #[test]
+    fn a_new_move_clears_redo() {
+        let mut game = board("...\n.*.\n...");
+        game.reveal(0, 0);
+        game.undo().unwrap();
+        assert!(game.can_redo());
+        game.cycle_mark(2, 2);
+        assert!(!game.can_redo());
+        assert_eq!(game.redo(), None);
+        assert_eq!(text(&game), "...\n.*.\n..f\n");
+    }
+
+    #[test]
+    fn losses_can_only_be_undone_in_practice() {
+        let mut game = board("2*\n*.");
+        game.reveal(1, 0);
+        assert!(!game.can_undo());
+        assert_eq!(game.undo(), None);
+        assert_eq!(text(&game), "2X\n*.\n");
+
+        game.config = game.config.with_practice(true);
+        let result = game.undo().unwrap();
+        assert_eq!(result.status, GameStatus::Playing);
+        // Every mine was shown by the loss, so they are all covered again.
+        assert!(result.changes.contains(&TileChange::Marked(
+            TileCoordinate { x: 0, y: 1 },
+            MineState::Empty
+        )));
+        assert_eq!(text(&game), "2*\n*.\n");
+
+        assert_eq!(
+            game.redo().unwrap().status,
+            GameStatus::Lost(TileCoordinate { x: 1, y: 0 })
+        );
+    }
+
+    #[test]
+    fn undoing_the_first_reveal_takes_back_the_mines() {
+        let config = Difficulty::Beginner.config().unwrap();
+        for seed in 0..50 {
+            let mut game = Game::with_seed(config, seed);
+            game.cycle_mark(8, 8);
+            game.reveal(4, 4);
+            game.undo().unwrap();
+            assert!(!game.are_mines_generated());
+            assert_eq!(game.mine_state(8, 8), MineState::Flag);
+
+            // Any tile is safe again, including one next to a former mine.
+            let x = (seed % 8) as i32;
+            game.reveal(x, 0);
+            let index = game.index_helper().compute_index(x, 0);
+            assert!(!game.is_mine(index));
+        }
+    }
+
+    #[test]
+    fn redoing_the_first_reveal_places_the_same_mines() {
+        let config = Difficulty::Intermediate.config().unwrap();
+        let mut game = Game::with_seed(config, 99);
+        game.reveal(3, 5);
+        let board = text(&game);
+        game.undo().unwrap();
+        game.redo().unwrap();
+        assert_eq!(text(&game), board);
+        assert_eq!(game.generation_outcome(), Some(GenerationOutcome::Random));
+    }
+
+    #[test]
+    fn rings_are_delayed_one_step_apart() {
+        let game = board("*1.\n11.\n...");
+        let (indices, tiles_per_ring) = game.tiles_in_rings(2, 2);
+        let step = Duration::from_millis(40);
+        let delays = ring_delays(indices, tiles_per_ring, step);
+        let ms = Duration::from_millis;
+        assert_eq!(
+            delays,
+            [
+                (8, ms(0)),
+                (4, ms(40)),
+                (7, ms(40)),
+                (5, ms(40)),
+                (0, ms(80)),
+                (3, ms(80)),
+                (6, ms(80)),
+                (1, ms(80)),
+                (2, ms(80)),
+            ]
+        );
+
+        // Rings without mines are skipped, so the next mine follows directly.
+        let (indices, mines_per_ring) = game.mines_in_rings(2, 2);
+        assert_eq!(
+            ring_delays(indices, mines_per_ring, step),
+            [(8, ms(0)), (0, ms(40))]
+        );
+    }
+
+    #[test]
+    fn opening_start_mode_reveals_an_area() {
+        let config = Difficulty::Expert
+            .config()
+            .unwrap()
+            .with_start_mode(StartMode::Opening { radius: 1 })
+            .unwrap();
+        for seed in 0..200 {
+            let mut game = Game::with_seed(config, seed);
+            game.reveal(15, 8);
+            assert_eq!(
+                game.neighbor_count(game.index_helper().compute_index(15, 8)),
+                0
+            );
+            assert!(game.current_board().changes.len() >= 9);
+        }
+    }
+}
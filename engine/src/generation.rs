@@ -0,0 +1,172 @@
+use crate::index_helper::IndexHelper;
+use crate::solver::{deduce, Verdict, VisibleBoard, VisibleTile};
+use std::collections::VecDeque;
+
+/// The number of boards tried by `Generation::no_guess` before giving up.
+pub const DEFAULT_NO_GUESS_ATTEMPTS: u32 = 1000;
+
+/// How mines are laid out once the first tile is revealed.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum Generation {
+    /// Mines are placed uniformly at random outside of the safe zone.
+    #[default]
+    Random,
+    /// Random boards are generated until one can be solved from the first
+    /// click by deduction alone. At most `max_attempts` boards are tried (at
+    /// least one); if none of them passes, the last one is kept.
+    NoGuess { max_attempts: u32 },
+}
+
+impl Generation {
+    pub fn no_guess() -> Self {
+        Generation::NoGuess {
+            max_attempts: DEFAULT_NO_GUESS_ATTEMPTS,
+        }
+    }
+}
+
+/// What happened when the mines of a game were generated.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GenerationOutcome {
+    /// The board was generated at random and never checked.
+    Random,
+    /// The board can be solved without guessing. Carries the number of boards
+    /// that were tried.
+    NoGuess { attempts: u32 },
+    /// No board within the attempt budget could be solved without guessing, so
+    /// the last random board was kept. It may require guessing.
+    BudgetExhausted { attempts: u32 },
+}
+
+/// Plays the board from `start_index` using only deductions from the visible
+/// tiles, and returns whether every safe tile could be revealed that way.
+pub(crate) fn is_solvable_without_guessing(
+    index_helper: &IndexHelper,
+    neighbor_counts: &[i32],
+    num_mines: i32,
+    start_index: usize,
+) -> bool {
+    let mut board = VisibleBoard::new(index_helper.width(), index_helper.height(), num_mines);
+    let mut covered_safe_tiles = neighbor_counts.iter().filter(|count| **count >= 0).count();
+
+    covered_safe_tiles -= reveal(&mut board, neighbor_counts, start_index);
+    while covered_safe_tiles > 0 {
+        let deductions = deduce(&board);
+        if deductions.is_empty() {
+            // Only a guess could make progress from here.
+            return false;
+        }
+
+        for deduction in deductions {
+            match deduction.verdict {
+                Verdict::Mine => board.set_tile(deduction.index, VisibleTile::Mine),
+                Verdict::Safe => {
+                    covered_safe_tiles -= reveal(&mut board, neighbor_counts, deduction.index)
+                }
+            }
+        }
+    }
+
+    true
+}
+
+/// Reveals a safe tile, cascading through zeros the same way `Game` does.
+/// Returns the number of newly revealed tiles.
+fn reveal(board: &mut VisibleBoard, neighbor_counts: &[i32], index: usize) -> usize {
+    if board.tile(index) != VisibleTile::Covered {
+        return 0;
+    }
+
+    let index_helper = *board.index_helper();
+    let mut revealed = 0;
+    let mut sweeps: VecDeque<usize> = VecDeque::new();
+    board.set_tile(index, VisibleTile::Revealed(neighbor_counts[index]));
+    revealed += 1;
+    sweeps.push_back(index);
+
+    while let Some(index) = sweeps.pop_front() {
+        if neighbor_counts[index] != 0 {
+            continue;
+        }
+
+        let x = index_helper.compute_x_from_index(index);
+        let y = index_helper.compute_y_from_index(index);
+        for neighbor in index_helper.neighbor_indices(x, y) {
+            if board.tile(neighbor) == VisibleTile::Covered {
+                board.set_tile(neighbor, VisibleTile::Revealed(neighbor_counts[neighbor]));
+                revealed += 1;
+                sweeps.push_back(neighbor);
+            }
+        }
+    }
+
+    revealed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::difficulty::Difficulty;
+    use crate::game::{Game, GameStatus};
+
+    #[test]
+    fn no_guess_boards_are_solved_by_deduction_from_the_first_click() {
+        let config = Difficulty::Intermediate
+            .config()
+            .unwrap()
+            .with_generation(Generation::no_guess());
+        let mut game = Game::with_seed(config, 2024);
+        game.reveal(8, 8);
+        assert!(matches!(
+            game.generation_outcome(),
+            Some(GenerationOutcome::NoGuess { .. })
+        ));
+
+        while !game.is_game_over() {
+            let deductions = deduce(&VisibleBoard::from_game(&game, true));
+            assert!(!deductions.is_empty(), "the solver had to guess");
+            for deduction in deductions {
+                let x = game.index_helper().compute_x_from_index(deduction.index);
+                let y = game.index_helper().compute_y_from_index(deduction.index);
+                match deduction.verdict {
+                    Verdict::Mine => game.cycle_mark(x, y),
+                    Verdict::Safe => game.reveal(x, y),
+                };
+            }
+        }
+        assert_eq!(game.status(), GameStatus::Won);
+    }
+
+    #[test]
+    fn the_last_board_is_kept_when_the_budget_runs_out() {
+        // So many mines that the first click cannot open anything.
+        let config = GameConfig::new(9, 9, 60).unwrap();
+        let mut random = Game::with_seed(config, 7);
+        random.reveal(4, 4);
+
+        for max_attempts in [0, 1] {
+            let config = config.with_generation(Generation::NoGuess { max_attempts });
+            let mut game = Game::with_seed(config, 7);
+            game.reveal(4, 4);
+            assert_eq!(
+                game.generation_outcome(),
+                Some(GenerationOutcome::BudgetExhausted { attempts: 1 })
+            );
+            assert_eq!(game.status(), GameStatus::Playing);
+            // With a single attempt, that board is the random one.
+            for index in 0..81 {
+                assert_eq!(game.is_mine(index), random.is_mine(index));
+            }
+        }
+
+        let config = config.with_generation(Generation::NoGuess { max_attempts: 5 });
+        let mut game = Game::with_seed(config, 7);
+        game.reveal(4, 4);
+        assert_eq!(
+            game.generation_outcome(),
+            Some(GenerationOutcome::BudgetExhausted { attempts: 5 })
+        );
+        assert!(!game.is_mine(game.index_helper().compute_index(4, 4)));
+    }
+}
@@ -0,0 +1,215 @@
+use crate::game::Game;
+use crate::probability::mine_probabilities;
+use crate::solver::{deduce, Deduction, Verdict, VisibleBoard, VisibleTile};
+use crate::tile::{MineState, TileCoordinate};
+use std::fmt;
+
+/// A suggestion for the next move, based only on what the player can see.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Hint {
+    /// A tile that is certainly safe or certainly a mine.
+    Deduction(Deduction),
+    /// Nothing can be deduced, so this is the covered tile that is least
+    /// likely to hold a mine. When the odds could only be approximated, it
+    /// is the tile with the lowest approximation, which need not be the
+    /// safest.
+    Guess {
+        tile: TileCoordinate,
+        probability: f64,
+        is_exact: bool,
+    },
+    /// The mines are only placed by the first reveal, so this tile is safe.
+    FirstMove(TileCoordinate),
+}
+
+impl Hint {
+    /// The tile the hint is about.
+    pub fn tile(&self) -> TileCoordinate {
+        match self {
+            Hint::Deduction(deduction) => deduction.tile,
+            Hint::Guess { tile, .. } => *tile,
+            Hint::FirstMove(tile) => *tile,
+        }
+    }
+}
+
+impl fmt::Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Hint::Deduction(deduction) => write!(f, "{}", deduction),
+            Hint::Guess {
+                tile,
+                probability,
+                is_exact: true,
+            } => write!(
+                f,
+                "nothing can be deduced, ({}, {}) is the safest guess with a {:.0}% chance of a mine",
+                tile.x,
+                tile.y,
+                probability * 100.0
+            ),
+            Hint::Guess {
+                tile,
+                probability,
+                is_exact: false,
+            } => write!(
+                f,
+                "nothing can be deduced and the odds are too many to count, ({}, {}) has roughly a {:.0}% chance of a mine",
+                tile.x,
+                tile.y,
+                probability * 100.0
+            ),
+            Hint::FirstMove(tile) => write!(
+                f,
+                "({}, {}) is safe: the mines are only placed by the first reveal",
+                tile.x, tile.y
+            ),
+        }
+    }
+}
+
+/// Finds a hint for `game`. Safe tiles are preferred over mines, and mines
+/// that are already flagged are not worth a hint. Flags are not trusted, so
+/// a wrongly flagged tile can come back as safe.
+pub(crate) fn find_hint(game: &Game) -> Option<Hint> {
+    if game.is_game_over() {
+        return None;
+    }
+    if !game.are_mines_generated() {
+        return first_move(game);
+    }
+
+    let board = VisibleBoard::from_game(game, false);
+    let deductions = deduce(&board);
+    let is_flagged = |index: usize| game.mine_states()[index] == MineState::Flag;
+    let deduction = deductions
+        .iter()
+        .find(|deduction| deduction.verdict == Verdict::Safe)
+        .or_else(|| {
+            deductions.iter().find(|deduction| {
+                deduction.verdict == Verdict::Mine && !is_flagged(deduction.index)
+            })
+        });
+    if let Some(deduction) = deduction {
+        return Some(Hint::Deduction(*deduction));
+    }
+
+    let probabilities = mine_probabilities(&board)?;
+    let values = &probabilities.values;
+    let index_helper = game.index_helper();
+    (0..values.len())
+        .filter(|index| board.tile(*index) == VisibleTile::Covered && !is_flagged(*index))
+        .min_by(|a, b| values[*a].partial_cmp(&values[*b]).unwrap())
+        .map(|index| Hint::Guess {
+            tile: TileCoordinate {
+                x: index_helper.compute_x_from_index(index),
+                y: index_helper.compute_y_from_index(index),
+            },
+            probability: values[index],
+            is_exact: probabilities.is_exact,
+        })
+}
+
+/// Any unmarked tile is safe before the first reveal. The middle of the board
+/// is suggested, since it keeps the most room around the opening.
+fn first_move(game: &Game) -> Option<Hint> {
+    let center = TileCoordinate {
+        x: game.width() / 2,
+        y: game.height() / 2,
+    };
+    let index_helper = game.index_helper();
+    let is_unmarked = |tile: &TileCoordinate| game.mine_state(tile.x, tile.y) == MineState::Empty;
+    let tile = Some(center).filter(is_unmarked).or_else(|| {
+        (0..game.mine_states().len())
+            .map(|index| TileCoordinate {
+                x: index_helper.compute_x_from_index(index),
+                y: index_helper.compute_y_from_index(index),
+            })
+            .find(is_unmarked)
+    })?;
+    Some(Hint::FirstMove(tile))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::notation::parse_board;
+
+    fn tile(x: i32, y: i32) -> TileCoordinate {
+        TileCoordinate { x, y }
+    }
+
+    #[test]
+    fn first_move_is_safe_without_a_probability() {
+        let mut game = Game::with_seed(GameConfig::new(9, 9, 10).unwrap(), 3);
+        let hint = find_hint(&game).unwrap();
+        assert_eq!(hint, Hint::FirstMove(tile(4, 4)));
+        assert_eq!(
+            hint.to_string(),
+            "(4, 4) is safe: the mines are only placed by the first reveal"
+        );
+        assert!(!game.are_mines_generated());
+
+        game.cycle_mark(4, 4);
+        assert_eq!(find_hint(&game), Some(Hint::FirstMove(tile(0, 0))));
+    }
+
+    #[test]
+    fn safe_deductions_come_first() {
+        let game = parse_board("*..\n11.").unwrap();
+        let hint = find_hint(&game).unwrap();
+        assert_eq!(hint.tile(), tile(2, 0));
+        assert!(matches!(hint, Hint::Deduction(deduction) if deduction.verdict == Verdict::Safe));
+    }
+
+    #[test]
+    fn flagged_mines_are_not_hinted() {
+        let game = parse_board("*.*\n121").unwrap();
+        assert_eq!(find_hint(&game).unwrap().tile(), tile(2, 0));
+        let game = parse_board("*.F\n121").unwrap();
+        assert_eq!(find_hint(&game).unwrap().tile(), tile(0, 0));
+    }
+
+    #[test]
+    fn guesses_pick_the_safest_tile() {
+        // The 1 holds one mine among three tiles, the other five covered
+        // tiles share the remaining one.
+        let game = parse_board("1..\n.*.\n..*").unwrap();
+        match find_hint(&game).unwrap() {
+            Hint::Guess {
+                tile,
+                probability,
+                is_exact,
+            } => {
+                assert!(probability < 1.0 / 3.0);
+                assert!(tile.x == 2 || tile.y == 2);
+                assert!(is_exact);
+            }
+            hint => panic!("expected a guess, got {:?}", hint),
+        }
+    }
+
+    #[test]
+    fn approximate_guesses_are_not_called_the_safest() {
+        let guess = |is_exact| Hint::Guess {
+            tile: tile(3, 1),
+            probability: 0.25,
+            is_exact,
+        };
+        assert_eq!(
+            guess(true).to_string(),
+            "nothing can be deduced, (3, 1) is the safest guess with a 25% chance of a mine"
+        );
+        assert_eq!(
+            guess(false).to_string(),
+            "nothing can be deduced and the odds are too many to count, (3, 1) has roughly a 25% chance of a mine"
+        );
+    }
+
+    #[test]
+    fn finished_games_have_no_hint() {
+        assert_eq!(find_hint(&parse_board("X1\n11").unwrap()), None);
+        assert_eq!(find_hint(&parse_board("*1\n11").unwrap()), None);
+    }
+}
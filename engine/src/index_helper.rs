@@ -0,0 +1,53 @@
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct IndexHelper {
+    width: i32,
+    height: i32,
+}
+
+impl IndexHelper {
+    pub fn new(width: i32, height: i32) -> Self {
+        Self { width, height }
+    }
+
+    pub fn compute_index(&self, x: i32, y: i32) -> usize {
+        (x * self.height + y) as usize
+    }
+
+    pub fn compute_x_from_index(&self, index: usize) -> i32 {
+        index as i32 / self.height
+    }
+
+    pub fn compute_y_from_index(&self, index: usize) -> i32 {
+        index as i32 % self.height
+    }
+
+    pub fn is_in_bounds(&self, x: i32, y: i32) -> bool {
+        (x >= 0 && x < self.width) && (y >= 0 && y < self.height)
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn tile_count(&self) -> usize {
+        (self.width * self.height) as usize
+    }
+
+    /// Returns the indices of the up to eight in-bounds tiles around (x, y).
+    pub fn neighbor_indices(&self, x: i32, y: i32) -> Vec<usize> {
+        let mut result = Vec::with_capacity(8);
+        for neighbor_x in (x - 1)..=(x + 1) {
+            for neighbor_y in (y - 1)..=(y + 1) {
+                if (neighbor_x != x || neighbor_y != y) && self.is_in_bounds(neighbor_x, neighbor_y)
+                {
+                    result.push(self.compute_index(neighbor_x, neighbor_y));
+                }
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,39 @@
+pub mod args;
+mod bindings;
+mod bot;
+mod command;
+mod config;
+mod difficulty;
+mod file_format;
+mod game;
+mod generation;
+mod hint;
+mod index_helper;
+mod minesweeper;
+mod notation;
+pub mod palette;
+mod probability;
+mod renderer;
+mod replay;
+mod save;
+mod solver;
+mod tile;
+
+pub use bindings::{Bindings, Gesture, Input, PointerButton};
+pub use bot::{play_game, Bot, BotMove, BotReport, SolverBot};
+pub use command::{Command, ParseCommandError};
+pub use config::{ConfigError, GameConfig, StartMode, MAX_DIMENSION};
+pub use difficulty::{Difficulty, ParseDifficultyError};
+pub use file_format::FormatError;
+pub use game::{ring_delays, Game, GameStatus, MoveResult, TileChange};
+pub use generation::{Generation, GenerationOutcome, DEFAULT_NO_GUESS_ATTEMPTS};
+pub use hint::Hint;
+pub use index_helper::IndexHelper;
+pub use minesweeper::Minesweeper;
+pub use notation::{parse_board, print_board};
+pub use probability::{mine_probabilities, Probabilities};
+pub use renderer::{BoardRenderer, Point, RecordingRenderer, RenderCommand, Size};
+pub use replay::{Replay, ReplayEvent, ReplayPlayer, TimedEvent};
+pub use save::SavedGame;
+pub use solver::{deduce, Clue, Deduction, Reason, Verdict, VisibleBoard, VisibleTile};
+pub use tile::{MarkCycle, MineState, ParseMarkCycleError, TileCoordinate};
@@ -0,0 +1,872 @@
+use crate::command::Command;
+use crate::config::GameConfig;
+use crate::difficulty::Difficulty;
+use crate::game::{Game, GameStatus, MoveResult, TileChange};
+use crate::hint::Hint;
+use crate::probability::mine_probabilities;
+use crate::renderer::{BoardRenderer, Point, Size};
+use crate::replay::{Replay, ReplayEvent};
+use crate::save::{write_game, SavedGame};
+use crate::solver::VisibleBoard;
+use crate::tile::{MineState, TileCoordinate};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub struct Minesweeper<R: BoardRenderer> {
+    ui: R,
+
+    game: Game,
+    last_tile: Option<TileCoordinate>,
+    /// Where the pointer was last seen, so that the tile under it can be found
+    /// again when the board moves. Keyboard selection forgets it.
+    last_point: Option<Point>,
+    /// Whether a chord is held down, and the covered tiles it shows pressed.
+    is_chord_pressed: bool,
+    pressed_tiles: Vec<TileCoordinate>,
+    show_probabilities: bool,
+    /// The hint given by the last command, if it was `Command::Hint`.
+    last_hint: Option<Hint>,
+
+    parent_size: Size,
+    replay: Option<Replay>,
+    game_started: Instant,
+    /// Time played before the game was resumed from a save.
+    elapsed_before: Duration,
+    /// The time on the clock when the game ended.
+    elapsed_at_end: Option<Duration>,
+}
+
+impl<R: BoardRenderer> Minesweeper<R> {
+    /// Starts a game of `config` on `ui`, with the mine layout determined by
+    /// `seed` if one is given.
+    pub fn new(
+        ui: R,
+        parent_size: &Size,
+        config: GameConfig,
+        seed: Option<u64>,
+    ) -> Result<Self, R::Error> {
+        let game = match seed {
+            Some(seed) => Game::with_seed(config, seed),
+            None => Game::new(config),
+        };
+        let mut result = Self {
+            ui,
+
+            game,
+            last_tile: None,
+            last_point: None,
+            is_chord_pressed: false,
+            pressed_tiles: Vec::new(),
+            show_probabilities: false,
+            last_hint: None,
+
+            parent_size: *parent_size,
+            replay: None,
+            game_started: Instant::now(),
+            elapsed_before: Duration::from_secs(0),
+            elapsed_at_end: None,
+        };
+
+        result.show_new_game()?;
+        result.ui.resize(parent_size)?;
+
+        Ok(result)
+    }
+
+    pub fn ui(&self) -> &R {
+        &self.ui
+    }
+
+    /// For renderers that have to be asked to draw. Changing tiles through it
+    /// puts the renderer out of step with the game.
+    pub fn ui_mut(&mut self) -> &mut R {
+        &mut self.ui
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.game.seed()
+    }
+
+    /// The recording of the current game so far, or `None` if the game was
+    /// resumed from a save, since the moves made before it are not known.
+    pub fn replay(&self) -> Option<&Replay> {
+        self.replay.as_ref()
+    }
+
+    /// How long the current game has been played, including the time before
+    /// it was saved. The clock stops when the game ends.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed_at_end
+            .unwrap_or_else(|| self.elapsed_before + self.game_started.elapsed())
+    }
+
+    /// The hint from the last command, for showing its reason. It is `None`
+    /// unless that command was `Command::Hint` and the solver found a move.
+    pub fn last_hint(&self) -> Option<&Hint> {
+        self.last_hint.as_ref()
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        Difficulty::from_dimensions(self.game.width(), self.game.height(), self.game.num_mines())
+    }
+
+    /// Abandons the current game and starts a new one with the given board
+    /// dimensions and mine count.
+    pub fn new_game(&mut self, config: GameConfig) -> Result<(), R::Error> {
+        self.start_game(Game::new(config))
+    }
+
+    /// Starts a new game with the current dimensions whose mine layout is
+    /// determined by `seed`.
+    pub fn new_game_with_seed(&mut self, seed: u64) -> Result<(), R::Error> {
+        let game = Game::with_seed(self.game.config(), seed);
+        self.start_game(game)
+    }
+
+    /// Continues a saved game where it was left off. A finished game is
+    /// shown the way its end animation leaves it, with every mine of a lost
+    /// game uncovered, but the animation is not played again.
+    pub fn resume(&mut self, saved: SavedGame) -> Result<(), R::Error> {
+        let board = saved.game.current_board();
+        self.start_game(saved.game)?;
+        self.replay = None;
+        self.elapsed_before = saved.elapsed;
+        self.update_tiles(&board.changes)?;
+        if let GameStatus::Lost(_) = board.status {
+            // The changes already show the mines that were revealed.
+            let covered_mines: Vec<usize> = (0..self.game.mine_states().len())
+                .filter(|index| {
+                    self.game.is_mine(*index)
+                        && self.game.mine_states()[*index] != MineState::Revealed
+                })
+                .collect();
+            let index_helper = *self.game.index_helper();
+            for index in covered_mines {
+                self.ui.update_tile_as_mine(&TileCoordinate {
+                    x: index_helper.compute_x_from_index(index),
+                    y: index_helper.compute_y_from_index(index),
+                })?;
+            }
+        }
+        if board.status != GameStatus::Playing {
+            self.elapsed_at_end = Some(saved.elapsed);
+        }
+        self.update_probabilities()
+    }
+
+    /// Writes the current game in the format read by `SavedGame::load`.
+    pub fn save<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_game(writer, &self.game, self.elapsed())
+    }
+
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.save(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn is_showing_probabilities(&self) -> bool {
+        self.show_probabilities
+    }
+
+    /// Turns the mine probability overlay on or off. While it is on, it is
+    /// refreshed after every move.
+    pub fn set_show_probabilities(&mut self, show: bool) -> Result<(), R::Error> {
+        self.show_probabilities = show;
+        self.update_probabilities()
+    }
+
+    pub fn toggle_probabilities(&mut self) -> Result<(), R::Error> {
+        self.set_show_probabilities(!self.show_probabilities)
+    }
+
+    pub fn on_pointer_moved(&mut self, point: &Point) -> Result<(), R::Error> {
+        self.record(ReplayEvent::PointerMoved(*point));
+        self.last_point = Some(*point);
+        self.hover(point)
+    }
+
+    /// Selects the tile under `point`, unless it is already revealed, and
+    /// moves a held chord along with it.
+    fn hover(&mut self, point: &Point) -> Result<(), R::Error> {
+        if self.game.is_game_over() {
+            return Ok(());
+        }
+
+        let selected_tile = if let Some(tile) = self.ui.hit_test(point)? {
+            self.last_tile = Some(tile);
+            if self.game.mine_state(tile.x, tile.y) != MineState::Revealed {
+                Some(tile)
+            } else {
+                None
+            }
+        } else {
+            self.last_tile = None;
+            None
+        };
+        self.ui.select_tile(selected_tile)?;
+        self.update_pressed_tiles()
+    }
+
+    /// The tile under the pointer, or the tile last selected with the
+    /// keyboard. This is the tile a chord acts on.
+    pub fn current_tile(&self) -> Option<TileCoordinate> {
+        self.last_tile
+    }
+
+    /// Selects a tile without the pointer, for keyboard control. Unlike the
+    /// pointer, this also selects revealed tiles, so that the selection
+    /// always shows where the keyboard is. Pressing on a selected revealed
+    /// tile chords it.
+    pub fn select_tile(&mut self, tile: TileCoordinate) -> Result<(), R::Error> {
+        self.record(ReplayEvent::TileSelected(tile));
+        if self.game.is_game_over() || !self.game.index_helper().is_in_bounds(tile.x, tile.y) {
+            return Ok(());
+        }
+
+        self.last_point = None;
+        self.last_tile = Some(tile);
+        self.ui.select_tile(Some(tile))?;
+        self.update_pressed_tiles()
+    }
+
+    /// Moves the selection by `dx` tiles to the right and `dy` tiles down,
+    /// stopping at the edges of the board. It starts from the selected tile,
+    /// or else the last tile under the pointer. Without either, the top left
+    /// tile is selected instead.
+    pub fn move_selection(&mut self, dx: i32, dy: i32) -> Result<(), R::Error> {
+        let tile = match self.ui.current_selected_tile().or(self.last_tile) {
+            Some(tile) => TileCoordinate {
+                x: (tile.x + dx).clamp(0, self.game.width() - 1),
+                y: (tile.y + dy).clamp(0, self.game.height() - 1),
+            },
+            None => TileCoordinate { x: 0, y: 0 },
+        };
+        self.select_tile(tile)
+    }
+
+    pub fn on_parent_size_changed(&mut self, new_size: &Size) -> Result<(), R::Error> {
+        self.record(ReplayEvent::ParentSizeChanged(*new_size));
+        self.parent_size = *new_size;
+        self.ui.resize(new_size)?;
+        // The board moved under the pointer.
+        match self.last_point {
+            Some(point) => self.hover(&point),
+            None => Ok(()),
+        }
+    }
+
+    /// Carries out `command`. Reveal, mark, chord, undo, redo and hint are
+    /// recorded in the replay; the others move the selection, which records
+    /// itself, or start a new game.
+    ///
+    /// While the end of a game is being shown, every command is ignored. The
+    /// pointer and a held chord need no such lock, since they already do
+    /// nothing once the game is over.
+    pub fn execute(&mut self, command: Command) -> Result<(), R::Error> {
+        if self.ui.is_animation_playing() {
+            self.last_hint = None;
+            return Ok(());
+        }
+        self.execute_unlocked(command)
+    }
+
+    /// Carries out `command` even while an animation plays. Replays use it,
+    /// since the commands they hold were carried out when they were recorded
+    /// and their animations run on the clock rather than in replay time.
+    pub(crate) fn execute_unlocked(&mut self, command: Command) -> Result<(), R::Error> {
+        self.last_hint = None;
+        match command {
+            Command::Reveal => self.press(false),
+            Command::Mark => self.press(true),
+            Command::Chord => {
+                self.record(ReplayEvent::Command(Command::Chord));
+                self.chord_last_tile()
+            }
+            Command::MoveLeft => self.move_selection(-1, 0),
+            Command::MoveRight => self.move_selection(1, 0),
+            Command::MoveUp => self.move_selection(0, -1),
+            Command::MoveDown => self.move_selection(0, 1),
+            Command::Undo => self.undo().map(|_| ()),
+            Command::Redo => self.redo().map(|_| ()),
+            Command::Hint => self.hint(),
+            Command::ToggleProbabilities => self.toggle_probabilities(),
+            Command::Restart => self.new_game(self.game.config()),
+            Command::NewGame(difficulty) => {
+                // The presets leave room for the largest opening we offer,
+                // but a custom size may not. Such a command does nothing.
+                let current_config = self.game.config();
+                match difficulty
+                    .config()
+                    .and_then(|config| config.with_start_mode(current_config.start_mode()))
+                {
+                    Ok(config) => self.new_game(
+                        config
+                            .with_generation(current_config.generation())
+                            .with_practice(current_config.is_practice())
+                            .with_mark_cycle(current_config.mark_cycle()),
+                    ),
+                    Err(_) => Ok(()),
+                }
+            }
+            // The front-end closes itself.
+            Command::Quit => Ok(()),
+        }
+    }
+
+    /// Reveals or marks the selected tile. Without a covered tile selected,
+    /// revealing chords the current tile instead.
+    fn press(&mut self, is_mark: bool) -> Result<(), R::Error> {
+        if self.game.is_game_over() {
+            // Starting a new game clears the selection, so the press does
+            // nothing else.
+            return self.new_game(self.game.config());
+        }
+
+        self.record(ReplayEvent::Command(if is_mark {
+            Command::Mark
+        } else {
+            Command::Reveal
+        }));
+        // Only the keyboard selects revealed tiles, and those are chorded
+        // like a revealed tile under the pointer.
+        let current_selection = self
+            .ui
+            .current_selected_tile()
+            .filter(|tile| self.game.mine_state(tile.x, tile.y) != MineState::Revealed);
+        if let Some(current_selection) = current_selection {
+            let result = if is_mark {
+                self.game
+                    .cycle_mark(current_selection.x, current_selection.y)
+            } else {
+                let mines_were_generated = self.game.are_mines_generated();
+                let result = self.game.reveal(current_selection.x, current_selection.y);
+                if !mines_were_generated && self.game.are_mines_generated() {
+                    self.show_mines_for_debugging()?;
+                }
+                result
+            };
+            self.apply_move_result(result)?;
+        } else {
+            if is_mark {
+                // Marking needs a covered tile
+                return Ok(());
+            }
+            self.chord_last_tile()?;
+        }
+        Ok(())
+    }
+
+    /// Takes back the last move. Returns whether there was a move to undo.
+    fn undo(&mut self) -> Result<bool, R::Error> {
+        self.record(ReplayEvent::Command(Command::Undo));
+        let was_game_over = self.game.is_game_over();
+        match self.game.undo() {
+            Some(result) => {
+                if was_game_over {
+                    self.ui.stop_animations()?;
+                }
+                self.apply_move_result(result)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Plays the last undone move again. Returns whether there was a move to
+    /// redo.
+    fn redo(&mut self) -> Result<bool, R::Error> {
+        self.record(ReplayEvent::Command(Command::Redo));
+        match self.game.redo() {
+            Some(result) => {
+                self.apply_move_result(result)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Asks the solver for a move and selects the tile it is about. Every
+    /// hint counts towards `Game::hints_used`.
+    fn hint(&mut self) -> Result<(), R::Error> {
+        self.record(ReplayEvent::Command(Command::Hint));
+        self.last_hint = self.game.hint();
+        if let Some(hint) = &self.last_hint {
+            self.ui.select_tile(Some(hint.tile()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Starts a chord on the current tile, for a middle click or both buttons
+    /// at once. Until it is released, the covered tiles it would reveal are
+    /// shown pressed.
+    pub fn on_chord_pressed(&mut self) -> Result<(), R::Error> {
+        self.record(ReplayEvent::ChordPressed);
+        if self.game.is_game_over() {
+            return Ok(());
+        }
+
+        self.is_chord_pressed = true;
+        self.update_pressed_tiles()
+    }
+
+    /// Finishes a chord started with `on_chord_pressed` on the tile that is
+    /// current now.
+    pub fn on_chord_released(&mut self) -> Result<(), R::Error> {
+        self.record(ReplayEvent::ChordReleased);
+        if !self.is_chord_pressed {
+            return Ok(());
+        }
+
+        self.is_chord_pressed = false;
+        self.update_pressed_tiles()?;
+        self.chord_last_tile()
+    }
+
+    pub fn is_chord_pressed(&self) -> bool {
+        self.is_chord_pressed
+    }
+
+    /// Shows the covered tiles around a held chord as pressed, and puts back
+    /// the ones it no longer covers. Flagged and question-marked tiles are
+    /// never pressed, since a chord does not reveal them.
+    fn update_pressed_tiles(&mut self) -> Result<(), R::Error> {
+        let mut pressed_tiles = Vec::new();
+        if let (true, Some(tile)) = (self.is_chord_pressed, self.last_tile) {
+            let index_helper = self.game.index_helper();
+            let mut indices = index_helper.neighbor_indices(tile.x, tile.y);
+            indices.push(index_helper.compute_index(tile.x, tile.y));
+            for index in indices {
+                let state = self.game.mine_states()[index];
+                if state == MineState::Empty {
+                    pressed_tiles.push(TileCoordinate {
+                        x: index_helper.compute_x_from_index(index),
+                        y: index_helper.compute_y_from_index(index),
+                    });
+                }
+            }
+        }
+
+        for tile in &self.pressed_tiles {
+            let state = self.game.mine_state(tile.x, tile.y);
+            if !pressed_tiles.contains(tile) && state != MineState::Revealed {
+                self.ui.update_tile_with_state(tile, state)?;
+            }
+        }
+        for tile in &pressed_tiles {
+            if !self.pressed_tiles.contains(tile) {
+                self.ui.update_tile_as_pressed(tile)?;
+            }
+        }
+        self.pressed_tiles = pressed_tiles;
+        Ok(())
+    }
+
+    fn chord_last_tile(&mut self) -> Result<(), R::Error> {
+        // OK, we're outside of the unrevealed/flagged/etc tiles, but we SHOULD be at last_tile
+        if let Some(cur_tile) = self.last_tile {
+            let result = self.game.chord(cur_tile.x, cur_tile.y);
+            self.apply_move_result(result)?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn start_game(&mut self, game: Game) -> Result<(), R::Error> {
+        self.game = game;
+        self.show_new_game()
+    }
+
+    /// Draws the game from scratch, and starts its clock and its replay.
+    fn show_new_game(&mut self) -> Result<(), R::Error> {
+        self.ui.reset(self.game.width(), self.game.height())?;
+
+        // Hit testing depends on the size, so every replay starts with it.
+        self.replay = Some(Replay::new(self.game.config(), self.game.seed()));
+        self.game_started = Instant::now();
+        self.elapsed_before = Duration::from_secs(0);
+        self.elapsed_at_end = None;
+        self.record(ReplayEvent::ParentSizeChanged(self.parent_size));
+
+        self.last_tile = None;
+        self.is_chord_pressed = false;
+        self.pressed_tiles.clear();
+        self.update_probabilities()?;
+
+        Ok(())
+    }
+
+    fn record(&mut self, event: ReplayEvent) {
+        if let Some(replay) = &mut self.replay {
+            replay.push(self.game_started.elapsed(), event);
+        }
+    }
+
+    fn apply_move_result(&mut self, result: MoveResult) -> Result<(), R::Error> {
+        // Undoing the end of a game starts the clock again.
+        self.elapsed_at_end = match result.status {
+            GameStatus::Playing => None,
+            _ => Some(self.elapsed()),
+        };
+
+        self.update_tiles(&result.changes)?;
+
+        match result.status {
+            GameStatus::Lost(hit_tile) => {
+                // We hit a mine! Setup and play an animation while locking any input.
+
+                // First, hide the selection visual and reset the selection
+                self.ui.select_tile(None)?;
+
+                let (mine_indices, mines_per_ring) =
+                    self.game.mines_in_rings(hit_tile.x, hit_tile.y);
+                self.ui.play_mine_animations(mine_indices, mines_per_ring)?;
+            }
+            GameStatus::Won => {
+                self.ui.select_tile(None)?;
+
+                // The ripple starts where the winning move did.
+                let origin = result.changes.iter().find_map(|change| match change {
+                    TileChange::Revealed(tile_coordinate, _) => Some(*tile_coordinate),
+                    _ => None,
+                });
+                if let Some(origin) = origin {
+                    let (tile_indices, tiles_per_ring) =
+                        self.game.tiles_in_rings(origin.x, origin.y);
+                    self.ui.play_win_animation(tile_indices, tiles_per_ring)?;
+                }
+            }
+            GameStatus::Playing => {}
+        }
+
+        self.update_probabilities()
+    }
+
+    fn update_tiles(&mut self, changes: &[TileChange]) -> Result<(), R::Error> {
+        for change in changes {
+            match change {
+                TileChange::Revealed(tile_coordinate, count) => self
+                    .ui
+                    .update_tile_with_mine_count(tile_coordinate, *count)?,
+                TileChange::Mine(tile_coordinate) => {
+                    self.ui.update_tile_as_mine(tile_coordinate)?
+                }
+                TileChange::Marked(tile_coordinate, state) => {
+                    self.ui.update_tile_with_state(tile_coordinate, *state)?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn update_probabilities(&mut self) -> Result<(), R::Error> {
+        if !self.show_probabilities || self.game.is_game_over() {
+            return self.ui.hide_probabilities();
+        }
+
+        // Flags are the player's guesses, so the overlay does not trust them.
+        let board = VisibleBoard::from_game(&self.game, false);
+        match mine_probabilities(&board) {
+            Some(probabilities) => {
+                let probabilities: Vec<Option<f64>> = probabilities
+                    .values
+                    .into_iter()
+                    .zip(self.game.mine_states())
+                    .map(|(probability, state)| match state {
+                        MineState::Revealed => None,
+                        _ => Some(probability),
+                    })
+                    .collect();
+                self.ui.show_probabilities(&probabilities)
+            }
+            None => self.ui.hide_probabilities(),
+        }
+    }
+
+    fn show_mines_for_debugging(&mut self) -> Result<(), R::Error> {
+        // DEBUG
+        if cfg!(feature = "show-mines") {
+            // Shown as the last mark of the cycle, which is a question mark
+            // unless those are disabled.
+            let mark = *self.game.config().mark_cycle().marks().last().unwrap();
+            let index_helper = *self.game.index_helper();
+            for index in 0..self.game.mine_states().len() {
+                if self.game.is_mine(index) {
+                    let tile_coordinate = TileCoordinate {
+                        x: index_helper.compute_x_from_index(index),
+                        y: index_helper.compute_y_from_index(index),
+                    };
+                    self.ui.update_tile_with_state(&tile_coordinate, mark)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_board;
+    use crate::renderer::{RecordingRenderer, RenderCommand};
+
+    fn tile(x: i32, y: i32) -> TileCoordinate {
+        TileCoordinate { x, y }
+    }
+
+    /// A game of `board` on a recording renderer that has not recorded
+    /// anything yet.
+    fn minesweeper(board: &str) -> Minesweeper<RecordingRenderer> {
+        let mut minesweeper = Minesweeper::new(
+            RecordingRenderer::new(),
+            &Size::new(100.0, 100.0),
+            GameConfig::default(),
+            None,
+        )
+        .unwrap();
+        minesweeper.start_game(parse_board(board).unwrap()).unwrap();
+        minesweeper.ui_mut().take_commands();
+        minesweeper
+    }
+
+    fn point_at(x: i32, y: i32) -> Point {
+        Point::new(x as f32 + 0.5, y as f32 + 0.5)
+    }
+
+    #[test]
+    fn pointer_selects_covered_tiles() {
+        let mut minesweeper = minesweeper("1*\n..");
+        minesweeper.on_pointer_moved(&point_at(0, 1)).unwrap();
+        minesweeper.on_pointer_moved(&point_at(0, 0)).unwrap();
+        assert_eq!(
+            minesweeper.ui_mut().take_commands(),
+            [
+                RenderCommand::SelectTile(Some(tile(0, 1))),
+                RenderCommand::SelectTile(None),
+            ]
+        );
+    }
+
+    #[test]
+    fn reveal_updates_the_revealed_tiles() {
+        let mut minesweeper = minesweeper("...\n.*.\n...");
+        minesweeper.on_pointer_moved(&point_at(0, 0)).unwrap();
+        minesweeper.ui_mut().take_commands();
+        minesweeper.execute(Command::Reveal).unwrap();
+        assert_eq!(
+            minesweeper.ui_mut().take_commands(),
+            [
+                RenderCommand::UpdateTileWithMineCount(tile(0, 0), 1),
+                RenderCommand::HideProbabilities,
+            ]
+        );
+    }
+
+    #[test]
+    fn keyboard_moves_and_marks() {
+        let mut minesweeper = minesweeper("...\n.*.\n...");
+        minesweeper.execute(Command::MoveDown).unwrap();
+        minesweeper.execute(Command::MoveRight).unwrap();
+        minesweeper.execute(Command::MoveDown).unwrap();
+        minesweeper.execute(Command::Mark).unwrap();
+        assert_eq!(
+            minesweeper.ui_mut().take_commands(),
+            [
+                RenderCommand::SelectTile(Some(tile(0, 0))),
+                RenderCommand::SelectTile(Some(tile(1, 0))),
+                RenderCommand::SelectTile(Some(tile(1, 1))),
+                RenderCommand::UpdateTileWithState(tile(1, 1), MineState::Flag),
+                RenderCommand::HideProbabilities,
+            ]
+        );
+    }
+
+    #[test]
+    fn loss_plays_the_mine_animation() {
+        let mut minesweeper = minesweeper("2*\n*.");
+        minesweeper.on_pointer_moved(&point_at(1, 0)).unwrap();
+        minesweeper.ui_mut().take_commands();
+        minesweeper.execute(Command::Reveal).unwrap();
+        assert_eq!(
+            minesweeper.ui_mut().take_commands(),
+            [
+                RenderCommand::UpdateTileAsMine(tile(1, 0)),
+                RenderCommand::SelectTile(None),
+                // The hit mine first, then the ring around it.
+                RenderCommand::PlayMineAnimations(vec![2, 1], vec![1, 1]),
+                RenderCommand::HideProbabilities,
+            ]
+        );
+    }
+
+    #[test]
+    fn win_plays_the_win_animation() {
+        let mut minesweeper = minesweeper("*1.\n11.\n...");
+        minesweeper.on_pointer_moved(&point_at(2, 2)).unwrap();
+        minesweeper.ui_mut().take_commands();
+        minesweeper.execute(Command::Reveal).unwrap();
+        assert_eq!(
+            minesweeper.ui_mut().take_commands(),
+            [
+                RenderCommand::UpdateTileWithMineCount(tile(2, 2), 0),
+                RenderCommand::UpdateTileWithMineCount(tile(2, 1), 0),
+                RenderCommand::UpdateTileWithMineCount(tile(1, 2), 0),
+                RenderCommand::UpdateTileWithMineCount(tile(2, 0), 0),
+                RenderCommand::UpdateTileWithMineCount(tile(0, 2), 0),
+                RenderCommand::SelectTile(None),
+                // Ring by ring outwards from the revealed tile.
+                RenderCommand::PlayWinAnimation(vec![8, 4, 7, 5, 0, 3, 6, 1, 2], vec![1, 3, 5]),
+                RenderCommand::HideProbabilities,
+            ]
+        );
+        assert_eq!(minesweeper.game().status(), GameStatus::Won);
+    }
+
+    /// `board` resumed after 42 seconds, with the commands from before
+    /// resuming cleared.
+    fn resumed(board: &str) -> Minesweeper<RecordingRenderer> {
+        let mut minesweeper = minesweeper("..\n..");
+        let saved = SavedGame::new(parse_board(board).unwrap(), Duration::from_secs(42));
+        minesweeper.resume(saved).unwrap();
+        minesweeper
+    }
+
+    #[test]
+    fn resuming_a_lost_game_shows_every_mine_without_the_animation() {
+        let mut minesweeper = resumed("2X\n*.");
+        assert_eq!(
+            minesweeper.ui_mut().take_commands(),
+            [
+                RenderCommand::Reset(2, 2),
+                RenderCommand::HideProbabilities,
+                RenderCommand::UpdateTileWithMineCount(tile(0, 0), 2),
+                RenderCommand::UpdateTileAsMine(tile(1, 0)),
+                RenderCommand::UpdateTileAsMine(tile(0, 1)),
+                RenderCommand::HideProbabilities,
+            ]
+        );
+        assert_eq!(minesweeper.elapsed(), Duration::from_secs(42));
+
+        // Nothing waits for an animation, so the next press starts a new game.
+        minesweeper.execute(Command::Reveal).unwrap();
+        assert_eq!(minesweeper.game().status(), GameStatus::Playing);
+    }
+
+    #[test]
+    fn resuming_a_won_game_does_not_play_the_win_animation() {
+        let mut minesweeper = resumed("*1\n11");
+        let commands = minesweeper.ui_mut().take_commands();
+        assert_eq!(commands.len(), 6);
+        assert!(commands.contains(&RenderCommand::UpdateTileWithMineCount(tile(1, 1), 1)));
+        assert!(!commands
+            .iter()
+            .any(|command| matches!(command, RenderCommand::PlayWinAnimation(..))));
+        assert_eq!(minesweeper.elapsed(), Duration::from_secs(42));
+    }
+
+    #[test]
+    fn commands_wait_for_the_end_of_the_game_to_be_shown() {
+        let mut minesweeper = minesweeper("*1.\n11.\n...");
+        minesweeper.on_pointer_moved(&point_at(2, 2)).unwrap();
+        minesweeper.execute(Command::Reveal).unwrap();
+        minesweeper.ui_mut().take_commands();
+
+        minesweeper.ui_mut().set_animation_playing(true);
+        for command in [
+            Command::Undo,
+            Command::Redo,
+            Command::Hint,
+            Command::Restart,
+            Command::NewGame(Difficulty::Expert),
+            Command::Reveal,
+            Command::ToggleProbabilities,
+        ] {
+            minesweeper.execute(command).unwrap();
+        }
+        assert_eq!(minesweeper.ui_mut().take_commands(), []);
+        assert_eq!(minesweeper.game().status(), GameStatus::Won);
+        assert!(minesweeper.last_hint().is_none());
+
+        minesweeper.ui_mut().set_animation_playing(false);
+        minesweeper.execute(Command::Undo).unwrap();
+        assert_eq!(minesweeper.game().status(), GameStatus::Playing);
+    }
+
+    #[test]
+    fn held_chord_presses_covered_neighbors() {
+        let mut minesweeper = minesweeper("F1.\n11.\n...");
+        minesweeper.on_pointer_moved(&point_at(1, 0)).unwrap();
+        minesweeper.ui_mut().take_commands();
+
+        minesweeper.on_chord_pressed().unwrap();
+        let pressed = minesweeper.ui_mut().take_commands();
+        assert_eq!(pressed.len(), 2);
+        assert!(pressed.contains(&RenderCommand::UpdateTileAsPressed(tile(2, 0))));
+        assert!(pressed.contains(&RenderCommand::UpdateTileAsPressed(tile(2, 1))));
+
+        // Releasing puts the pressed tiles back before the chord reveals them.
+        minesweeper.on_chord_released().unwrap();
+        let released = minesweeper.ui_mut().take_commands();
+        assert!(released[..2].contains(&RenderCommand::UpdateTileWithState(
+            tile(2, 0),
+            MineState::Empty
+        )));
+        assert!(released[..2].contains(&RenderCommand::UpdateTileWithState(
+            tile(2, 1),
+            MineState::Empty
+        )));
+        assert!(released.contains(&RenderCommand::UpdateTileWithMineCount(tile(2, 0), 0)));
+        assert_eq!(minesweeper.game().status(), GameStatus::Won);
+    }
+
+    #[test]
+    fn held_chord_skips_question_marks() {
+        let mut minesweeper = minesweeper("F1q\n11.\n...");
+        minesweeper.on_pointer_moved(&point_at(1, 0)).unwrap();
+        minesweeper.ui_mut().take_commands();
+
+        minesweeper.on_chord_pressed().unwrap();
+        assert_eq!(
+            minesweeper.ui_mut().take_commands(),
+            vec![RenderCommand::UpdateTileAsPressed(tile(2, 1))]
+        );
+
+        // The chord leaves the question mark alone, just like the preview.
+        minesweeper.on_chord_released().unwrap();
+        assert_eq!(minesweeper.game().mine_state(2, 0), MineState::Question);
+        assert_eq!(minesweeper.game().mine_state(2, 1), MineState::Revealed);
+    }
+
+    #[test]
+    fn chord_press_follows_the_pointer() {
+        let mut minesweeper = minesweeper("F1.\n11.\n...");
+        minesweeper.on_pointer_moved(&point_at(1, 0)).unwrap();
+        minesweeper.on_chord_pressed().unwrap();
+        minesweeper.ui_mut().take_commands();
+
+        // Off the board, nothing is pressed any more.
+        minesweeper
+            .on_pointer_moved(&Point::new(-1.0, -1.0))
+            .unwrap();
+        let commands = minesweeper.ui_mut().take_commands();
+        assert_eq!(commands[0], RenderCommand::SelectTile(None));
+        assert!(commands[1..].iter().all(|command| matches!(
+            command,
+            RenderCommand::UpdateTileWithState(_, MineState::Empty)
+        )));
+        assert_eq!(commands.len(), 3);
+
+        // Releasing there does not chord.
+        minesweeper.on_chord_released().unwrap();
+        assert_eq!(minesweeper.ui_mut().take_commands(), []);
+        assert_eq!(minesweeper.game().status(), GameStatus::Playing);
+    }
+}
@@ -0,0 +1,261 @@
+use crate::config::GameConfig;
+use crate::file_format::{FormatError, LineReader};
+use crate::game::{Game, GameState, GameStatus};
+use crate::generation::GenerationOutcome;
+use crate::index_helper::IndexHelper;
+use crate::tile::{MineState, TileCoordinate};
+
+/// Reads a board written in the text notation:
+///
+/// ```text
+/// 1F1..
+/// 111.*
+/// ..q.f
+/// ```
+///
+/// Each line is a row, from top to bottom, with one character per tile.
+/// Lowercase letters and `.` are safe tiles, uppercase letters and `*` are
+/// mines:
+///
+/// | Tile            | Safe          | Mine |
+/// |-----------------|---------------|------|
+/// | Covered         | `.`           | `*`  |
+/// | Flagged         | `f`           | `F`  |
+/// | Question mark   | `q`           | `Q`  |
+/// | Revealed        | `0` to `8`    | `X`  |
+///
+/// Revealed numbers must match the mines around them. Leading and trailing
+/// whitespace and blank lines are ignored, so boards can be indented inside
+/// other text. The game is over if a mine is revealed or every safe tile is.
+///
+/// The board has to pass `GameConfig::new` like any other, so at least one
+/// tile is free of mines. That keeps the first click of a restarted board
+/// safe and lets a game read from a board be saved and loaded again.
+pub fn parse_board(text: &str) -> Result<Game, FormatError> {
+    let mut reader = LineReader::new(text.as_bytes());
+    let mut rows: Vec<(usize, Vec<char>)> = Vec::new();
+    while let Some(fields) = reader.next_fields()? {
+        reader.expect_count(&fields, 1)?;
+        let row: Vec<char> = fields[0].chars().collect();
+        if let Some((_, first_row)) = rows.first() {
+            if row.len() != first_row.len() {
+                return Err(reader.invalid(format!(
+                    "expected {} tiles, found {}",
+                    first_row.len(),
+                    row.len()
+                )));
+            }
+        }
+        rows.push((reader.line_number(), row));
+    }
+    let width = rows.first().map_or(0, |(_, row)| row.len()) as i32;
+    let height = rows.len() as i32;
+
+    let index_helper = IndexHelper::new(width, height);
+    let tile_count = (width * height) as usize;
+    let mut mines = vec![false; tile_count];
+    let mut mine_states = vec![MineState::Empty; tile_count];
+    let mut numbers = Vec::new();
+    let mut hit_tiles = Vec::new();
+    for (y, (line, row)) in rows.iter().enumerate() {
+        for (x, tile) in row.iter().enumerate() {
+            let coordinate = TileCoordinate {
+                x: x as i32,
+                y: y as i32,
+            };
+            let index = index_helper.compute_index(coordinate.x, coordinate.y);
+            let (is_mine, state) = match tile {
+                '.' => (false, MineState::Empty),
+                '*' => (true, MineState::Empty),
+                'f' => (false, MineState::Flag),
+                'F' => (true, MineState::Flag),
+                'q' => (false, MineState::Question),
+                'Q' => (true, MineState::Question),
+                '0'..='8' => {
+                    numbers.push((coordinate, tile.to_digit(10).unwrap() as i32));
+                    (false, MineState::Revealed)
+                }
+                'X' => {
+                    hit_tiles.push(coordinate);
+                    (true, MineState::Revealed)
+                }
+                _ => {
+                    return Err(FormatError::InvalidLine {
+                        line: *line,
+                        message: format!("invalid tile '{}'", tile),
+                    })
+                }
+            };
+            mines[index] = is_mine;
+            mine_states[index] = state;
+        }
+    }
+
+    let mine_count = mines.iter().filter(|mine| **mine).count() as i32;
+    let config = GameConfig::new(width, height, mine_count)?;
+    let safe_tiles_covered =
+        (0..tile_count).any(|index| !mines[index] && mine_states[index] != MineState::Revealed);
+    let status = match hit_tiles.as_slice() {
+        [] if !safe_tiles_covered => GameStatus::Won,
+        [] => GameStatus::Playing,
+        [hit_tile] => GameStatus::Lost(*hit_tile),
+        _ => {
+            return Err(FormatError::InvalidState(
+                "a game ends when the first mine is revealed".to_string(),
+            ))
+        }
+    };
+
+    let game = Game::from_state(GameState {
+        config,
+        seed: 0,
+        mines: Some((mines, GenerationOutcome::Random)),
+        neighbor_counts: None,
+        mine_states,
+        status,
+        hints_used: 0,
+        undos_used: 0,
+    })
+    .map_err(FormatError::InvalidState)?;
+
+    for (coordinate, number) in numbers {
+        let index = index_helper.compute_index(coordinate.x, coordinate.y);
+        if game.neighbor_count(index) != number {
+            return Err(FormatError::InvalidState(format!(
+                "the {} at ({}, {}) should be a {}",
+                number,
+                coordinate.x,
+                coordinate.y,
+                game.neighbor_count(index)
+            )));
+        }
+    }
+
+    Ok(game)
+}
+
+/// Writes `game` in the notation read by `parse_board`, one row per line.
+/// Returns `None` before the first reveal, since the mines are not placed
+/// yet and the notation has no way to leave them out.
+pub fn print_board(game: &Game) -> Option<String> {
+    if !game.are_mines_generated() {
+        return None;
+    }
+
+    let index_helper = game.index_helper();
+    let mut text = String::new();
+    for y in 0..game.height() {
+        for x in 0..game.width() {
+            let index = index_helper.compute_index(x, y);
+            let is_mine = game.is_mine(index);
+            let tile = match (game.mine_state(x, y), is_mine) {
+                (MineState::Empty, false) => '.',
+                (MineState::Empty, true) => '*',
+                (MineState::Flag, false) => 'f',
+                (MineState::Flag, true) => 'F',
+                (MineState::Question, false) => 'q',
+                (MineState::Question, true) => 'Q',
+                (MineState::Revealed, false) => {
+                    std::char::from_digit(game.neighbor_count(index) as u32, 10).unwrap()
+                }
+                (MineState::Revealed, true) => 'X',
+            };
+            text.push(tile);
+        }
+        text.push('\n');
+    }
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(text: &str) -> String {
+        match parse_board(text) {
+            Ok(_) => panic!("{:?} was accepted", text),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn boards_round_trip() {
+        for text in [
+            "1F1..\n111.*\n..q.f\n",
+            "..*\n.QF\n*..\n",
+            "X1\n11\n",
+            "*1\n11\n",
+            "0000\n0011\n001*\n",
+        ] {
+            let game = parse_board(text).unwrap();
+            assert_eq!(print_board(&game).as_deref(), Some(text));
+        }
+    }
+
+    #[test]
+    fn indentation_and_blank_lines_are_ignored() {
+        let game = parse_board("\n    1F1..\n    111.*\n\n    ..q.f\n").unwrap();
+        assert_eq!(print_board(&game).as_deref(), Some("1F1..\n111.*\n..q.f\n"));
+    }
+
+    #[test]
+    fn status_follows_the_board() {
+        assert_eq!(parse_board("1*\n..").unwrap().status(), GameStatus::Playing);
+        assert_eq!(parse_board("*1\n11").unwrap().status(), GameStatus::Won);
+        assert_eq!(
+            parse_board("X1\n11").unwrap().status(),
+            GameStatus::Lost(TileCoordinate { x: 0, y: 0 })
+        );
+    }
+
+    #[test]
+    fn boards_leave_a_tile_for_the_first_click() {
+        assert_eq!(
+            error("**\n**"),
+            "invalid game configuration: 4 mines do not fit on this board, at most 3 can be placed"
+        );
+        assert!(parse_board("*").is_err());
+
+        let game = parse_board("**\n*.").unwrap();
+        let mut restarted = Game::with_seed(game.config(), 1);
+        restarted.reveal(0, 0);
+        assert_eq!(restarted.status(), GameStatus::Won);
+    }
+
+    #[test]
+    fn boards_without_mines_cannot_be_printed() {
+        let game = Game::with_seed(GameConfig::new(3, 3, 1).unwrap(), 1);
+        assert_eq!(print_board(&game), None);
+    }
+
+    #[test]
+    fn wrong_numbers_are_rejected() {
+        assert_eq!(
+            error("1.\n.."),
+            "inconsistent board: the 1 at (0, 0) should be a 0"
+        );
+        assert_eq!(
+            error("*.\n.3"),
+            "inconsistent board: the 3 at (1, 1) should be a 1"
+        );
+    }
+
+    #[test]
+    fn ragged_rows_are_rejected() {
+        assert_eq!(error("...\n..\n..."), "line 2: expected 3 tiles, found 2");
+    }
+
+    #[test]
+    fn unknown_tiles_are_rejected() {
+        assert_eq!(error("..\n.z"), "line 2: invalid tile 'z'");
+        assert_eq!(error("... ..."), "line 1: expected 1 values, found 2");
+    }
+
+    #[test]
+    fn only_one_mine_can_be_hit() {
+        assert_eq!(
+            error("X2X"),
+            "inconsistent board: a game ends when the first mine is revealed"
+        );
+    }
+}
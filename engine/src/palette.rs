@@ -0,0 +1,124 @@
+use crate::tile::MineState;
+
+/// The size of a tile, in pixels, in every front-end that draws the board
+/// itself.
+pub const TILE_SIZE: f32 = 25.0;
+/// The gap between two tiles. Half of it is left around the edge of the
+/// board, and the selection frame is this thick.
+pub const TILE_MARGIN: f32 = 2.5;
+/// The radius of the dots that show a tile's number, as a fraction of the
+/// tile size.
+pub const DOT_RADIUS: f32 = 1.0 / 12.0;
+
+/// An 8-bit RGBA color that is not premultiplied.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 255 }
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+}
+
+/// The color behind the board.
+pub const BACKGROUND_COLOR: Color = Color::rgb(255, 255, 255);
+/// The color of a revealed mine and of the selection frame.
+pub const MINE_COLOR: Color = Color::rgb(255, 0, 0);
+pub const SELECTION_COLOR: Color = Color::rgb(255, 0, 0);
+pub const DOT_COLOR: Color = Color::rgb(0, 0, 0);
+/// The color that ripples over the board when a game is won, the named gold
+/// of the composition front-end.
+pub const WIN_COLOR: Color = Color::rgb(255, 215, 0);
+
+/// The color of a covered tile. These are the named colors the composition
+/// front-end uses: blue, orange and lime green.
+pub fn covered_color(state: MineState) -> Color {
+    match state {
+        MineState::Flag => Color::rgb(255, 165, 0),
+        MineState::Question => Color::rgb(50, 205, 50),
+        _ => Color::rgb(0, 0, 255),
+    }
+}
+
+/// The background of a revealed tile with `count` neighboring mines.
+pub fn mine_count_color(count: i32) -> Color {
+    match count {
+        1 => Color::rgb(173, 216, 230),
+        2 => Color::rgb(144, 238, 144),
+        3 => Color::rgb(255, 160, 122),
+        4 => Color::rgb(176, 196, 222),
+        5 => Color::rgb(147, 112, 219),
+        6 => Color::rgb(224, 255, 255),
+        7 => Color::rgb(128, 0, 0),
+        8 => Color::rgb(143, 188, 143),
+        _ => Color::rgb(245, 245, 245),
+    }
+}
+
+/// A translucent tint that goes from green for safe tiles to red for certain
+/// mines, drawn over covered tiles by the probability overlay.
+pub fn probability_color(probability: f64) -> Color {
+    let probability = probability.clamp(0.0, 1.0);
+    Color::rgba(
+        (255.0 * probability) as u8,
+        (255.0 * (1.0 - probability)) as u8,
+        0,
+        160,
+    )
+}
+
+/// The centers of the dots that show `count`, as fractions of the tile size
+/// from its top left corner.
+pub fn dot_positions(count: i32) -> Vec<(f32, f32)> {
+    const CENTER: (f32, f32) = (0.5, 0.5);
+    // Six dots in two columns, which 6, 7 and 8 build on.
+    const SIX: [(f32, f32); 6] = [
+        (0.25, 0.5),
+        (0.25, 0.75),
+        (0.75, 0.25),
+        (0.25, 0.25),
+        (0.75, 0.75),
+        (0.75, 0.5),
+    ];
+    let third = 1.0 / 3.0;
+    match count {
+        1 => vec![CENTER],
+        2 => vec![(third, 0.5), (third * 2.0, 0.5)],
+        3 => vec![CENTER, (0.25, 0.75), (0.75, 0.25)],
+        4 => vec![
+            (third, third),
+            (third * 2.0, third),
+            (third, third * 2.0),
+            (third * 2.0, third * 2.0),
+        ],
+        5 => vec![
+            CENTER,
+            (0.25, 0.75),
+            (0.75, 0.25),
+            (0.25, 0.25),
+            (0.75, 0.75),
+        ],
+        6 => SIX.to_vec(),
+        7 => {
+            let mut dots = SIX.to_vec();
+            dots.push(CENTER);
+            dots
+        }
+        8 => {
+            let mut dots = SIX.to_vec();
+            dots.push((0.5, third));
+            dots.push((0.5, third * 2.0));
+            dots
+        }
+        _ => Vec::new(),
+    }
+}
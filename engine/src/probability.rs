@@ -0,0 +1,701 @@
+use crate::solver::{VisibleBoard, VisibleTile};
+
+/// How many steps the layouts of one group may take to enumerate before it
+/// is approximated instead. This keeps the overlay responsive after every
+/// move; real boards stay far below it.
+const MAX_SEARCH_STEPS: usize = 250_000;
+
+/// The size of the pieces a group that is too slow to enumerate is cut into.
+/// A piece has at most 2^17 search steps, so it always finishes.
+const APPROXIMATE_GROUP_CELLS: usize = 16;
+
+/// The chance of a mine on every tile, as found by `mine_probabilities`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Probabilities {
+    /// Uses `IndexHelper` indexing: revealed tiles are 0.0 and known mines
+    /// are 1.0.
+    pub values: Vec<f64>,
+    /// Whether every value is exact. Otherwise some were approximated and
+    /// the lowest value need not belong to the safest tile.
+    pub is_exact: bool,
+}
+
+/// Computes the probability that each tile holds a mine, given only the
+/// visible board and assuming every layout consistent with it is equally
+/// likely. Returns `None` if no layout matches the board, which can only
+/// happen when known mines were marked wrongly.
+///
+/// Covered tiles next to a revealed number (the frontier) are split into
+/// independent groups whose layouts are enumerated. Every other covered tile
+/// is interchangeable with the rest, so they are counted as a single block
+/// that holds whatever mines the frontier leaves over. The enumeration is
+/// exponential in the size of a group in the worst case, so the odds are
+/// exact unless a stretch of numbers has too many layouts to count quickly.
+/// A group that takes more than `MAX_SEARCH_STEPS` is cut into smaller
+/// pieces that ignore the numbers spanning two pieces, and the result is
+/// marked as not exact.
+pub fn mine_probabilities(board: &VisibleBoard) -> Option<Probabilities> {
+    probabilities_within(board, MAX_SEARCH_STEPS)
+}
+
+fn probabilities_within(board: &VisibleBoard, max_steps: usize) -> Option<Probabilities> {
+    let tile_count = board.tiles().len();
+    let remaining_mines = board.remaining_mines();
+    if remaining_mines < 0 {
+        return None;
+    }
+    let remaining_mines = remaining_mines as usize;
+
+    let mut probabilities: Vec<f64> = board
+        .tiles()
+        .iter()
+        .map(|tile| match tile {
+            VisibleTile::Mine => 1.0,
+            _ => 0.0,
+        })
+        .collect();
+
+    let mut components = Vec::new();
+    let mut layouts = Vec::new();
+    let mut is_exact = true;
+    for component in find_components(board)? {
+        match component.enumerate(remaining_mines, max_steps) {
+            Enumeration::Layouts(component_layouts) => {
+                components.push(component);
+                layouts.push(component_layouts);
+            }
+            Enumeration::Impossible => return None,
+            Enumeration::TooSlow => {
+                is_exact = false;
+                for piece in component.split(APPROXIMATE_GROUP_CELLS) {
+                    // Leaving out numbers cannot rule out the layouts that
+                    // were possible before, so a piece only fails on a board
+                    // that has no layout at all.
+                    match piece.enumerate(remaining_mines, usize::MAX) {
+                        Enumeration::Layouts(piece_layouts) => {
+                            components.push(piece);
+                            layouts.push(piece_layouts);
+                        }
+                        Enumeration::Impossible | Enumeration::TooSlow => return None,
+                    }
+                }
+            }
+        }
+    }
+    let mut is_frontier = vec![false; tile_count];
+    for component in &components {
+        for cell in &component.cells {
+            is_frontier[*cell] = true;
+        }
+    }
+    let other_count = (0..tile_count)
+        .filter(|index| board.tile(*index) == VisibleTile::Covered && !is_frontier[*index])
+        .count();
+    let log_factorials = log_factorials(tile_count);
+    let log_choose = |n: usize, k: isize| -> f64 {
+        if k < 0 || k as usize > n {
+            f64::NEG_INFINITY
+        } else {
+            let k = k as usize;
+            log_factorials[n] - log_factorials[k] - log_factorials[n - k]
+        }
+    };
+
+    // Frontier tiles: weigh every mine count of the group by the number of
+    // ways the other groups and the block can hold the rest of the mines.
+    for (component_index, (component, layout)) in components.iter().zip(&layouts).enumerate() {
+        let others = layouts
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| *index != component_index)
+            .fold(vec![1.0], |total, (_, other)| {
+                convolve(&total, &other.counts)
+            });
+        let log_weights: Vec<f64> = (0..layout.counts.len())
+            .map(|mines| {
+                log_sum_exp(others.iter().enumerate().map(|(other_mines, count)| {
+                    count.ln()
+                        + log_choose(
+                            other_count,
+                            remaining_mines as isize - (mines + other_mines) as isize,
+                        )
+                }))
+            })
+            .collect();
+        let max_weight = log_weights
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        if max_weight == f64::NEG_INFINITY {
+            return None;
+        }
+        let weights: Vec<f64> = log_weights
+            .iter()
+            .map(|weight| (weight - max_weight).exp())
+            .collect();
+
+        let total: f64 = layout
+            .counts
+            .iter()
+            .zip(&weights)
+            .map(|(count, weight)| count * weight)
+            .sum();
+        for (cell, cell_counts) in component.cells.iter().zip(&layout.cell_counts) {
+            let mine_weight: f64 = cell_counts
+                .iter()
+                .zip(&weights)
+                .map(|(count, weight)| count * weight)
+                .sum();
+            probabilities[*cell] = mine_weight / total;
+        }
+    }
+
+    // Every other covered tile shares the mines left over by the frontier.
+    if other_count > 0 {
+        let frontier = layouts
+            .iter()
+            .fold(vec![1.0], |total, layout| convolve(&total, &layout.counts));
+        let log_weights: Vec<(f64, f64)> = frontier
+            .iter()
+            .enumerate()
+            .map(|(mines, count)| {
+                let left_over = remaining_mines as isize - mines as isize;
+                (
+                    count.ln() + log_choose(other_count, left_over),
+                    left_over as f64 / other_count as f64,
+                )
+            })
+            .collect();
+        let max_weight = log_weights
+            .iter()
+            .map(|(weight, _)| *weight)
+            .fold(f64::NEG_INFINITY, f64::max);
+        if max_weight == f64::NEG_INFINITY {
+            return None;
+        }
+        let (mut mine_weight, mut total) = (0.0, 0.0);
+        for (weight, density) in log_weights {
+            let weight = (weight - max_weight).exp();
+            mine_weight += weight * density;
+            total += weight;
+        }
+        let probability = mine_weight / total;
+        for (index, tile) in board.tiles().iter().enumerate() {
+            if *tile == VisibleTile::Covered && !is_frontier[index] {
+                probabilities[index] = probability;
+            }
+        }
+    }
+
+    Some(Probabilities {
+        values: probabilities,
+        is_exact,
+    })
+}
+
+/// A revealed number, restricted to the covered tiles of one component.
+struct Constraint {
+    /// Positions in `Component::cells`.
+    cells: Vec<usize>,
+    mines: usize,
+}
+
+/// Frontier tiles that are linked to each other through revealed numbers.
+struct Component {
+    /// Board indices, in the order they are enumerated.
+    cells: Vec<usize>,
+    constraints: Vec<Constraint>,
+}
+
+/// How many layouts of a component have a given number of mines, overall and
+/// with each of its cells holding a mine. Indexed by the number of mines.
+struct Layouts {
+    counts: Vec<f64>,
+    cell_counts: Vec<Vec<f64>>,
+}
+
+enum Enumeration {
+    Layouts(Layouts),
+    /// No layout satisfies the constraints.
+    Impossible,
+    /// The search ran out of steps before it was done.
+    TooSlow,
+}
+
+/// Splits the frontier into components. Returns `None` if a revealed number
+/// already touches too many known mines or too few covered tiles.
+fn find_components(board: &VisibleBoard) -> Option<Vec<Component>> {
+    let index_helper = board.index_helper();
+    let tile_count = board.tiles().len();
+
+    // (cells, mines) for every revealed number that touches covered tiles.
+    let mut constraints: Vec<(Vec<usize>, usize)> = Vec::new();
+    let mut constraints_of_cell: Vec<Vec<usize>> = vec![Vec::new(); tile_count];
+    for (index, tile) in board.tiles().iter().enumerate() {
+        if let VisibleTile::Revealed(count) = tile {
+            let x = index_helper.compute_x_from_index(index);
+            let y = index_helper.compute_y_from_index(index);
+            let neighbors = index_helper.neighbor_indices(x, y);
+            let known_mines = neighbors
+                .iter()
+                .filter(|neighbor| board.tile(**neighbor) == VisibleTile::Mine)
+                .count() as i32;
+            let cells: Vec<usize> = neighbors
+                .into_iter()
+                .filter(|neighbor| board.tile(*neighbor) == VisibleTile::Covered)
+                .collect();
+            let mines = count - known_mines;
+            if mines < 0 || mines as usize > cells.len() {
+                return None;
+            }
+            if cells.is_empty() {
+                continue;
+            }
+            for cell in &cells {
+                constraints_of_cell[*cell].push(constraints.len());
+            }
+            constraints.push((cells, mines as usize));
+        }
+    }
+
+    // Walk from cell to cell through shared constraints. The walk order also
+    // makes a good enumeration order, since each cell tends to complete a
+    // constraint that was started by the cells before it.
+    let mut visited_cells = vec![false; tile_count];
+    let mut visited_constraints = vec![false; constraints.len()];
+    let mut components = Vec::new();
+    for start in 0..tile_count {
+        if visited_cells[start] || constraints_of_cell[start].is_empty() {
+            continue;
+        }
+
+        let mut cells = vec![start];
+        let mut component_constraints = Vec::new();
+        visited_cells[start] = true;
+        let mut next = 0;
+        while next < cells.len() {
+            let cell = cells[next];
+            next += 1;
+            for constraint in &constraints_of_cell[cell] {
+                if visited_constraints[*constraint] {
+                    continue;
+                }
+                visited_constraints[*constraint] = true;
+                component_constraints.push(*constraint);
+                for other in &constraints[*constraint].0 {
+                    if !visited_cells[*other] {
+                        visited_cells[*other] = true;
+                        cells.push(*other);
+                    }
+                }
+            }
+        }
+
+        let constraints = component_constraints
+            .iter()
+            .map(|constraint| {
+                let (constraint_cells, mines) = &constraints[*constraint];
+                Constraint {
+                    cells: constraint_cells
+                        .iter()
+                        .map(|cell| cells.iter().position(|other| other == cell).unwrap())
+                        .collect(),
+                    mines: *mines,
+                }
+            })
+            .collect();
+        components.push(Component { cells, constraints });
+    }
+
+    Some(components)
+}
+
+impl Component {
+    /// Counts the layouts of the component that satisfy all of its
+    /// constraints with at most `max_mines` mines, giving up after
+    /// `max_steps` steps of the search.
+    fn enumerate(&self, max_mines: usize, max_steps: usize) -> Enumeration {
+        let mut constraints_of_cell = vec![Vec::new(); self.cells.len()];
+        for (index, constraint) in self.constraints.iter().enumerate() {
+            for cell in &constraint.cells {
+                constraints_of_cell[*cell].push(index);
+            }
+        }
+
+        let max_mines = max_mines.min(self.cells.len());
+        let mut search = Search {
+            constraints: &self.constraints,
+            constraints_of_cell,
+            max_mines,
+            steps_left: max_steps,
+            assigned_mines: vec![0; self.constraints.len()],
+            unassigned_cells: self
+                .constraints
+                .iter()
+                .map(|constraint| constraint.cells.len())
+                .collect(),
+            layout: vec![false; self.cells.len()],
+            mines: 0,
+            layouts: Layouts {
+                counts: vec![0.0; max_mines + 1],
+                cell_counts: vec![vec![0.0; max_mines + 1]; self.cells.len()],
+            },
+        };
+        if !search.visit(0) {
+            Enumeration::TooSlow
+        } else if search.layouts.counts.iter().all(|count| *count == 0.0) {
+            Enumeration::Impossible
+        } else {
+            Enumeration::Layouts(search.layouts)
+        }
+    }
+
+    /// Cuts the component into pieces of at most `cells` cells, in the order
+    /// of the walk so that neighboring cells tend to stay together. Each
+    /// piece keeps only the constraints that lie entirely inside it.
+    fn split(&self, cells: usize) -> Vec<Component> {
+        self.cells
+            .chunks(cells)
+            .enumerate()
+            .map(|(piece, piece_cells)| {
+                let start = piece * cells;
+                let range = start..start + piece_cells.len();
+                let constraints = self
+                    .constraints
+                    .iter()
+                    .filter(|constraint| constraint.cells.iter().all(|cell| range.contains(cell)))
+                    .map(|constraint| Constraint {
+                        cells: constraint.cells.iter().map(|cell| cell - start).collect(),
+                        mines: constraint.mines,
+                    })
+                    .collect();
+                Component {
+                    cells: piece_cells.to_vec(),
+                    constraints,
+                }
+            })
+            .collect()
+    }
+}
+
+/// The state of the backtracking search over the cells of a component.
+struct Search<'a> {
+    constraints: &'a [Constraint],
+    constraints_of_cell: Vec<Vec<usize>>,
+    max_mines: usize,
+    steps_left: usize,
+    assigned_mines: Vec<usize>,
+    unassigned_cells: Vec<usize>,
+    layout: Vec<bool>,
+    mines: usize,
+    layouts: Layouts,
+}
+
+impl<'a> Search<'a> {
+    /// Returns `false` if the search ran out of steps.
+    fn visit(&mut self, cell: usize) -> bool {
+        if self.steps_left == 0 {
+            return false;
+        }
+        self.steps_left -= 1;
+
+        if cell == self.layout.len() {
+            self.layouts.counts[self.mines] += 1.0;
+            for (index, is_mine) in self.layout.iter().enumerate() {
+                if *is_mine {
+                    self.layouts.cell_counts[index][self.mines] += 1.0;
+                }
+            }
+            return true;
+        }
+
+        for is_mine in [false, true].iter().copied() {
+            if is_mine && self.mines == self.max_mines {
+                continue;
+            }
+            let fits = self.constraints_of_cell[cell].iter().all(|constraint| {
+                let mines = self.constraints[*constraint].mines;
+                let assigned = self.assigned_mines[*constraint];
+                if is_mine {
+                    assigned < mines
+                } else {
+                    assigned + self.unassigned_cells[*constraint] > mines
+                }
+            });
+            if !fits {
+                continue;
+            }
+
+            for constraint in &self.constraints_of_cell[cell] {
+                self.unassigned_cells[*constraint] -= 1;
+                if is_mine {
+                    self.assigned_mines[*constraint] += 1;
+                }
+            }
+            self.layout[cell] = is_mine;
+            if is_mine {
+                self.mines += 1;
+            }
+
+            let finished = self.visit(cell + 1);
+
+            if is_mine {
+                self.mines -= 1;
+            }
+            self.layout[cell] = false;
+            for constraint in &self.constraints_of_cell[cell] {
+                self.unassigned_cells[*constraint] += 1;
+                if is_mine {
+                    self.assigned_mines[*constraint] -= 1;
+                }
+            }
+            if !finished {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Combines the layout counts of two independent groups, rescaled so that
+/// large boards do not overflow. Only the ratios between entries matter.
+fn convolve(first: &[f64], second: &[f64]) -> Vec<f64> {
+    let mut result = vec![0.0; first.len() + second.len() - 1];
+    for (i, a) in first.iter().enumerate() {
+        for (j, b) in second.iter().enumerate() {
+            result[i + j] += a * b;
+        }
+    }
+    let max = result.iter().copied().fold(0.0, f64::max);
+    if max > 0.0 {
+        for value in &mut result {
+            *value /= max;
+        }
+    }
+    result
+}
+
+fn log_sum_exp(values: impl Iterator<Item = f64>) -> f64 {
+    let values: Vec<f64> = values.collect();
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        return max;
+    }
+    max + values
+        .iter()
+        .map(|value| (value - max).exp())
+        .sum::<f64>()
+        .ln()
+}
+
+fn log_factorials(n: usize) -> Vec<f64> {
+    let mut result = Vec::with_capacity(n + 1);
+    result.push(0.0);
+    for i in 1..=n {
+        result.push(result[i - 1] + (i as f64).ln());
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::game::Game;
+    use crate::tile::MineState;
+
+    /// The probabilities found by trying every way to place the remaining
+    /// mines on the covered tiles.
+    fn brute_force(board: &VisibleBoard) -> Option<Vec<f64>> {
+        let covered: Vec<usize> = (0..board.tiles().len())
+            .filter(|index| board.tile(*index) == VisibleTile::Covered)
+            .collect();
+        let mut layout = board.tiles().to_vec();
+        let mut mine_counts = vec![0.0; layout.len()];
+        let mut total = 0.0;
+        place(
+            board,
+            &covered,
+            board.remaining_mines(),
+            &mut layout,
+            &mut mine_counts,
+            &mut total,
+        );
+        if total == 0.0 {
+            return None;
+        }
+
+        Some(
+            (0..layout.len())
+                .map(|index| match board.tile(index) {
+                    VisibleTile::Mine => 1.0,
+                    VisibleTile::Revealed(_) => 0.0,
+                    VisibleTile::Covered => mine_counts[index] / total,
+                })
+                .collect(),
+        )
+    }
+
+    fn place(
+        board: &VisibleBoard,
+        covered: &[usize],
+        mines: i32,
+        layout: &mut Vec<VisibleTile>,
+        mine_counts: &mut [f64],
+        total: &mut f64,
+    ) {
+        if mines == 0 {
+            let index_helper = board.index_helper();
+            let matches = (0..layout.len()).all(|index| match layout[index] {
+                VisibleTile::Revealed(count) => {
+                    let x = index_helper.compute_x_from_index(index);
+                    let y = index_helper.compute_y_from_index(index);
+                    index_helper
+                        .neighbor_indices(x, y)
+                        .iter()
+                        .filter(|neighbor| layout[**neighbor] == VisibleTile::Mine)
+                        .count() as i32
+                        == count
+                }
+                _ => true,
+            });
+            if matches {
+                *total += 1.0;
+                for index in 0..layout.len() {
+                    if layout[index] == VisibleTile::Mine {
+                        mine_counts[index] += 1.0;
+                    }
+                }
+            }
+            return;
+        }
+
+        for (position, cell) in covered.iter().enumerate() {
+            layout[*cell] = VisibleTile::Mine;
+            place(
+                board,
+                &covered[position + 1..],
+                mines - 1,
+                layout,
+                mine_counts,
+                total,
+            );
+            layout[*cell] = VisibleTile::Covered;
+        }
+    }
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!(
+                (actual - expected).abs() < 1e-9,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn fixtures_match_brute_force() {
+        for (text, trust_flags) in [
+            ("*.*\n121", false),
+            ("1.\n.*", false),
+            ("..*.\n.21.\n*...\n....", false),
+            (".*..\n.2..\n..*.\n0...", false),
+            ("F1..\n11..\n....\n..**", true),
+            ("F1..\n11..\n....\n..**", false),
+            ("...\n...\n..*", false),
+        ] {
+            let board = VisibleBoard::from_notation(text, trust_flags);
+            let expected = brute_force(&board).unwrap();
+            let probabilities = mine_probabilities(&board).unwrap();
+            assert_close(&probabilities.values, &expected);
+            assert!(probabilities.is_exact);
+        }
+    }
+
+    #[test]
+    fn played_games_match_brute_force() {
+        let config = GameConfig::new(5, 4, 4).unwrap();
+        for seed in 0..30 {
+            let mut game = Game::with_seed(config, seed);
+            let tile_count = config.tile_count() as usize;
+            for step in 0..tile_count {
+                let index = (seed as usize * 7 + step * 13) % tile_count;
+                let (x, y) = (index as i32 / 4, index as i32 % 4);
+                if (game.are_mines_generated() && game.is_mine(index))
+                    || game.mine_state(x, y) != MineState::Empty
+                {
+                    continue;
+                }
+                game.reveal(x, y);
+                if game.is_game_over() {
+                    break;
+                }
+
+                let board = VisibleBoard::from_game(&game, false);
+                let expected = brute_force(&board).unwrap();
+                assert_close(&mine_probabilities(&board).unwrap().values, &expected);
+            }
+        }
+    }
+
+    #[test]
+    fn impossible_boards_have_no_probabilities() {
+        // A second known mine next to the 1 at (1, 0) is one too many.
+        let mut board = VisibleBoard::from_notation("F1.\n11.\n...", true);
+        board.set_tile(7, VisibleTile::Mine);
+        assert_eq!(brute_force(&board), None);
+        assert_eq!(mine_probabilities(&board), None);
+    }
+
+    /// A long row of 2s between two covered rows, which has far too many
+    /// layouts to enumerate.
+    fn long_row_of_twos() -> VisibleBoard {
+        let width = 61;
+        let mut board = VisibleBoard::new(width, 3, 42);
+        for x in 0..width {
+            let index = board.index_helper().compute_index(x, 1);
+            board.set_tile(index, VisibleTile::Revealed(2));
+        }
+        board
+    }
+
+    fn assert_plausible(probabilities: &[f64], board: &VisibleBoard) {
+        assert!(probabilities
+            .iter()
+            .all(|probability| (0.0..=1.0).contains(probability)));
+        let expected_mines: f64 = probabilities.iter().sum();
+        assert!((expected_mines - board.num_mines() as f64).abs() < 1e-6);
+    }
+
+    #[test]
+    fn large_groups_are_approximated() {
+        let board = long_row_of_twos();
+        let components = find_components(&board).unwrap();
+        assert_eq!(components.len(), 1);
+        assert!(matches!(
+            components[0].enumerate(42, MAX_SEARCH_STEPS),
+            Enumeration::TooSlow
+        ));
+
+        let probabilities = mine_probabilities(&board).unwrap();
+        assert!(!probabilities.is_exact);
+        assert_plausible(&probabilities.values, &board);
+    }
+
+    #[test]
+    fn approximation_keeps_the_mine_count() {
+        for (text, trust_flags) in [
+            ("*.*\n121", false),
+            ("..*.\n.21.\n*...\n....", false),
+            ("F1..\n11..\n....\n..**", true),
+        ] {
+            let board = VisibleBoard::from_notation(text, trust_flags);
+            let probabilities = probabilities_within(&board, 0).unwrap();
+            assert!(!probabilities.is_exact);
+            assert_plausible(&probabilities.values, &board);
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use crate::tile::{MineState, TileCoordinate};
+use std::collections::VecDeque;
+use std::convert::Infallible;
+
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Size {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Size {
+    pub fn new(width: f32, height: f32) -> Self {
+        Self { width, height }
+    }
+}
+
+/// A front-end that can present the board. `Minesweeper` drives the game
+/// exclusively through this trait.
+pub trait BoardRenderer {
+    type Error;
+
+    /// Clears every tile back to the covered state and resizes the grid.
+    fn reset(&mut self, width: i32, height: i32) -> Result<(), Self::Error>;
+    fn resize(&mut self, new_size: &Size) -> Result<(), Self::Error>;
+    /// Maps a point in parent coordinates to the tile underneath it.
+    fn hit_test(&self, point: &Point) -> Result<Option<TileCoordinate>, Self::Error>;
+
+    fn select_tile(&mut self, tile_coordinate: Option<TileCoordinate>) -> Result<(), Self::Error>;
+    fn current_selected_tile(&self) -> Option<TileCoordinate>;
+
+    fn update_tile_with_state(
+        &mut self,
+        tile_coordinate: &TileCoordinate,
+        mine_state: MineState,
+    ) -> Result<(), Self::Error>;
+    fn update_tile_as_mine(&mut self, tile_coordinate: &TileCoordinate) -> Result<(), Self::Error>;
+    /// Shows a covered tile pushed down, as a preview of what a held chord
+    /// would reveal. It looks like a revealed tile without neighboring mines
+    /// until `update_tile_with_state` puts it back.
+    fn update_tile_as_pressed(
+        &mut self,
+        tile_coordinate: &TileCoordinate,
+    ) -> Result<(), Self::Error>;
+    fn update_tile_with_mine_count(
+        &mut self,
+        tile_coordinate: &TileCoordinate,
+        num_mines: i32,
+    ) -> Result<(), Self::Error>;
+
+    /// Animates the mines in `mine_indices`, one ring at a time. `mines_per_ring`
+    /// holds the number of consecutive indices that belong to each ring.
+    fn play_mine_animations(
+        &mut self,
+        mine_indices: VecDeque<usize>,
+        mines_per_ring: VecDeque<i32>,
+    ) -> Result<(), Self::Error>;
+    /// Celebrates a win with a ripple over the tiles in `tile_indices`, one
+    /// ring at a time, grouped like in `play_mine_animations`.
+    fn play_win_animation(
+        &mut self,
+        tile_indices: VecDeque<usize>,
+        tiles_per_ring: VecDeque<i32>,
+    ) -> Result<(), Self::Error>;
+    /// Whether an animation is still running. Input that would start a new
+    /// game is ignored until it finishes.
+    fn is_animation_playing(&self) -> bool;
+    /// Stops any running animation and puts the animated tiles back in place,
+    /// for example when a lost game is undone.
+    fn stop_animations(&mut self) -> Result<(), Self::Error>;
+
+    /// Tints tiles by their chance of holding a mine. `probabilities` uses
+    /// `IndexHelper` indexing; tiles with `None` are left untinted.
+    fn show_probabilities(&mut self, probabilities: &[Option<f64>]) -> Result<(), Self::Error>;
+    fn hide_probabilities(&mut self) -> Result<(), Self::Error>;
+}
+
+/// A call made on a `RecordingRenderer`.
+#[derive(Clone, PartialEq, Debug)]
+pub enum RenderCommand {
+    Reset(i32, i32),
+    Resize(Size),
+    SelectTile(Option<TileCoordinate>),
+    UpdateTileWithState(TileCoordinate, MineState),
+    UpdateTileAsMine(TileCoordinate),
+    UpdateTileAsPressed(TileCoordinate),
+    UpdateTileWithMineCount(TileCoordinate, i32),
+    PlayMineAnimations(Vec<usize>, Vec<i32>),
+    PlayWinAnimation(Vec<usize>, Vec<i32>),
+    StopAnimations,
+    ShowProbabilities(Vec<Option<f64>>),
+    HideProbabilities,
+}
+
+/// A renderer that draws nothing and remembers every call made on it. Tiles
+/// are laid out one unit apart, so the point (2.5, 3.5) hits the tile (2, 3).
+/// Animations finish at once unless `set_animation_playing` says otherwise.
+pub struct RecordingRenderer {
+    width: i32,
+    height: i32,
+    current_selection: Option<TileCoordinate>,
+    is_animation_playing: bool,
+    commands: Vec<RenderCommand>,
+}
+
+impl RecordingRenderer {
+    pub fn new() -> Self {
+        Self {
+            width: 0,
+            height: 0,
+            current_selection: None,
+            is_animation_playing: false,
+            commands: Vec::new(),
+        }
+    }
+
+    /// Makes an animation look like it is playing until this is called again
+    /// or the animations are stopped.
+    pub fn set_animation_playing(&mut self, is_playing: bool) {
+        self.is_animation_playing = is_playing;
+    }
+
+    pub fn commands(&self) -> &[RenderCommand] {
+        &self.commands
+    }
+
+    /// Returns the commands recorded so far and starts a new recording.
+    pub fn take_commands(&mut self) -> Vec<RenderCommand> {
+        std::mem::take(&mut self.commands)
+    }
+}
+
+impl Default for RecordingRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BoardRenderer for RecordingRenderer {
+    type Error = Infallible;
+
+    fn reset(&mut self, width: i32, height: i32) -> Result<(), Self::Error> {
+        self.width = width;
+        self.height = height;
+        self.current_selection = None;
+        self.commands.push(RenderCommand::Reset(width, height));
+        Ok(())
+    }
+
+    fn resize(&mut self, new_size: &Size) -> Result<(), Self::Error> {
+        self.commands.push(RenderCommand::Resize(*new_size));
+        Ok(())
+    }
+
+    fn hit_test(&self, point: &Point) -> Result<Option<TileCoordinate>, Self::Error> {
+        if point.x < 0.0 || point.y < 0.0 {
+            return Ok(None);
+        }
+
+        let x = point.x as i32;
+        let y = point.y as i32;
+        if x < self.width && y < self.height {
+            Ok(Some(TileCoordinate { x, y }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn select_tile(&mut self, tile_coordinate: Option<TileCoordinate>) -> Result<(), Self::Error> {
+        self.current_selection = tile_coordinate;
+        self.commands
+            .push(RenderCommand::SelectTile(tile_coordinate));
+        Ok(())
+    }
+
+    fn current_selected_tile(&self) -> Option<TileCoordinate> {
+        self.current_selection
+    }
+
+    fn update_tile_with_state(
+        &mut self,
+        tile_coordinate: &TileCoordinate,
+        mine_state: MineState,
+    ) -> Result<(), Self::Error> {
+        self.commands.push(RenderCommand::UpdateTileWithState(
+            *tile_coordinate,
+            mine_state,
+        ));
+        Ok(())
+    }
+
+    fn update_tile_as_mine(&mut self, tile_coordinate: &TileCoordinate) -> Result<(), Self::Error> {
+        self.commands
+            .push(RenderCommand::UpdateTileAsMine(*tile_coordinate));
+        Ok(())
+    }
+
+    fn update_tile_as_pressed(
+        &mut self,
+        tile_coordinate: &TileCoordinate,
+    ) -> Result<(), Self::Error> {
+        self.commands
+            .push(RenderCommand::UpdateTileAsPressed(*tile_coordinate));
+        Ok(())
+    }
+
+    fn update_tile_with_mine_count(
+        &mut self,
+        tile_coordinate: &TileCoordinate,
+        num_mines: i32,
+    ) -> Result<(), Self::Error> {
+        self.commands.push(RenderCommand::UpdateTileWithMineCount(
+            *tile_coordinate,
+            num_mines,
+        ));
+        Ok(())
+    }
+
+    fn play_mine_animations(
+        &mut self,
+        mine_indices: VecDeque<usize>,
+        mines_per_ring: VecDeque<i32>,
+    ) -> Result<(), Self::Error> {
+        self.commands.push(RenderCommand::PlayMineAnimations(
+            mine_indices.into_iter().collect(),
+            mines_per_ring.into_iter().collect(),
+        ));
+        Ok(())
+    }
+
+    fn play_win_animation(
+        &mut self,
+        tile_indices: VecDeque<usize>,
+        tiles_per_ring: VecDeque<i32>,
+    ) -> Result<(), Self::Error> {
+        self.commands.push(RenderCommand::PlayWinAnimation(
+            tile_indices.into_iter().collect(),
+            tiles_per_ring.into_iter().collect(),
+        ));
+        Ok(())
+    }
+
+    fn is_animation_playing(&self) -> bool {
+        self.is_animation_playing
+    }
+
+    fn stop_animations(&mut self) -> Result<(), Self::Error> {
+        self.is_animation_playing = false;
+        self.commands.push(RenderCommand::StopAnimations);
+        Ok(())
+    }
+
+    fn show_probabilities(&mut self, probabilities: &[Option<f64>]) -> Result<(), Self::Error> {
+        self.commands
+            .push(RenderCommand::ShowProbabilities(probabilities.to_vec()));
+        Ok(())
+    }
+
+    fn hide_probabilities(&mut self) -> Result<(), Self::Error> {
+        self.commands.push(RenderCommand::HideProbabilities);
+        Ok(())
+    }
+}
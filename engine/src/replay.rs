@@ -0,0 +1,538 @@
+use crate::command::Command;
+use crate::config::GameConfig;
+use crate::file_format::{
+    read_config, read_header, write_config, write_header, FormatError, LineReader,
+};
+use crate::game::Game;
+use crate::minesweeper::Minesweeper;
+use crate::renderer::{BoardRenderer, Point, Size};
+use crate::tile::TileCoordinate;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+const REPLAY_HEADER: &str = "minesweeper-replay";
+const REPLAY_VERSION: u32 = 1;
+
+/// An input received by `Minesweeper`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ReplayEvent {
+    PointerMoved(Point),
+    ParentSizeChanged(Size),
+    /// A tile selected with the keyboard.
+    TileSelected(TileCoordinate),
+    ChordPressed,
+    ChordReleased,
+    /// A command that changes the board: reveal, mark, chord, undo, redo or
+    /// hint.
+    Command(Command),
+}
+
+/// An input and when it happened, relative to the start of the game.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TimedEvent {
+    pub time: Duration,
+    pub event: ReplayEvent,
+}
+
+/// Everything needed to play a game again: its config, its seed and every
+/// input it received.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Replay {
+    config: GameConfig,
+    seed: u64,
+    events: Vec<TimedEvent>,
+}
+
+impl Replay {
+    pub fn new(config: GameConfig, seed: u64) -> Self {
+        Self {
+            config,
+            seed,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn config(&self) -> GameConfig {
+        self.config
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn events(&self) -> &[TimedEvent] {
+        &self.events
+    }
+
+    /// Adds an event. Events are expected in chronological order.
+    pub fn push(&mut self, time: Duration, event: ReplayEvent) {
+        self.events.push(TimedEvent { time, event });
+    }
+
+    /// The time of the last event.
+    pub fn duration(&self) -> Duration {
+        self.events
+            .last()
+            .map(|event| event.time)
+            .unwrap_or_default()
+    }
+
+    pub fn save<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_header(writer, REPLAY_HEADER, REPLAY_VERSION)?;
+        write_config(writer, &self.config)?;
+        writeln!(writer, "seed {}", self.seed)?;
+        writeln!(writer, "events {}", self.events.len())?;
+        for TimedEvent { time, event } in &self.events {
+            write!(writer, "{} ", time.as_nanos())?;
+            match event {
+                ReplayEvent::PointerMoved(point) => {
+                    writeln!(writer, "move {} {}", point.x, point.y)?
+                }
+                ReplayEvent::ParentSizeChanged(size) => {
+                    writeln!(writer, "resize {} {}", size.width, size.height)?
+                }
+                ReplayEvent::TileSelected(tile) => {
+                    writeln!(writer, "select {} {}", tile.x, tile.y)?
+                }
+                ReplayEvent::ChordPressed => writeln!(writer, "chord-press")?,
+                ReplayEvent::ChordReleased => writeln!(writer, "chord-release")?,
+                ReplayEvent::Command(command) => writeln!(writer, "{}", command)?,
+            }
+        }
+        Ok(())
+    }
+
+    pub fn load<R: BufRead>(reader: R) -> Result<Self, FormatError> {
+        let mut reader = LineReader::new(reader);
+        read_header(&mut reader, REPLAY_HEADER, REPLAY_VERSION)?;
+        let config = read_config(&mut reader)?;
+        let seed = reader.expect_value("seed")?;
+        let event_count: usize = reader.expect_value("events")?;
+
+        let mut replay = Self::new(config, seed);
+        for _ in 0..event_count {
+            let fields = reader.next_fields()?.ok_or(FormatError::UnexpectedEnd)?;
+            let time = Duration::from_nanos(reader.parse(&fields[0])?);
+            if time < replay.duration() {
+                return Err(reader.invalid("events are out of order".to_string()));
+            }
+
+            let values = &fields[1..];
+            let (keyword, values) = match values.split_first() {
+                Some((keyword, values)) => (keyword.as_str(), values),
+                None => return Err(reader.invalid("missing event".to_string())),
+            };
+            let event = match keyword {
+                "move" => {
+                    reader.expect_count(values, 2)?;
+                    ReplayEvent::PointerMoved(Point::new(
+                        reader.parse(&values[0])?,
+                        reader.parse(&values[1])?,
+                    ))
+                }
+                "resize" => {
+                    reader.expect_count(values, 2)?;
+                    ReplayEvent::ParentSizeChanged(Size::new(
+                        reader.parse(&values[0])?,
+                        reader.parse(&values[1])?,
+                    ))
+                }
+                "select" => {
+                    reader.expect_count(values, 2)?;
+                    ReplayEvent::TileSelected(TileCoordinate {
+                        x: reader.parse(&values[0])?,
+                        y: reader.parse(&values[1])?,
+                    })
+                }
+                "chord-press" | "chord-release" => {
+                    reader.expect_count(values, 0)?;
+                    match keyword {
+                        "chord-press" => ReplayEvent::ChordPressed,
+                        _ => ReplayEvent::ChordReleased,
+                    }
+                }
+                // Other commands do not change the board or are not
+                // recorded, so they cannot appear here.
+                "reveal" | "mark" | "chord" | "undo" | "redo" | "hint" => {
+                    reader.expect_count(values, 0)?;
+                    ReplayEvent::Command(reader.parse(keyword)?)
+                }
+                _ => return Err(reader.invalid(format!("unknown event '{}'", keyword))),
+            };
+            replay.push(time, event);
+        }
+
+        Ok(replay)
+    }
+
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.save(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, FormatError> {
+        Self::load(BufReader::new(File::open(path)?))
+    }
+}
+
+/// Plays a replay back through a `Minesweeper`, using the same entry points
+/// as live input so that hover selection is reproduced too.
+pub struct ReplayPlayer {
+    replay: Replay,
+    /// The index of the next event to play.
+    position: usize,
+    time: Duration,
+    speed: f64,
+    is_paused: bool,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            position: 0,
+            time: Duration::from_secs(0),
+            speed: 1.0,
+            is_paused: false,
+        }
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Starts the recorded game from the beginning.
+    pub fn start<R: BoardRenderer>(
+        &mut self,
+        minesweeper: &mut Minesweeper<R>,
+    ) -> Result<(), R::Error> {
+        self.position = 0;
+        self.time = Duration::from_secs(0);
+        minesweeper.start_game(Game::with_seed(self.replay.config, self.replay.seed))?;
+        self.play_until(minesweeper, self.time)
+    }
+
+    /// The position of the playback, in replay time.
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position == self.replay.events.len()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    pub fn set_paused(&mut self, is_paused: bool) {
+        self.is_paused = is_paused;
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Sets how fast replay time passes compared to real time.
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed.max(0.0);
+    }
+
+    /// Moves the playback forward by `elapsed` real time, scaled by the
+    /// speed, and plays every event that happened in between. Does nothing
+    /// while paused.
+    pub fn advance<R: BoardRenderer>(
+        &mut self,
+        minesweeper: &mut Minesweeper<R>,
+        elapsed: Duration,
+    ) -> Result<(), R::Error> {
+        if self.is_paused {
+            return Ok(());
+        }
+
+        let time = self.time + elapsed.mul_f64(self.speed);
+        self.play_until(minesweeper, time)
+    }
+
+    /// Jumps to `time`. Seeking backwards starts the game over and plays
+    /// every event up to `time` again.
+    pub fn seek<R: BoardRenderer>(
+        &mut self,
+        minesweeper: &mut Minesweeper<R>,
+        time: Duration,
+    ) -> Result<(), R::Error> {
+        if time < self.time {
+            self.start(minesweeper)?;
+        }
+        self.play_until(minesweeper, time)
+    }
+
+    fn play_until<R: BoardRenderer>(
+        &mut self,
+        minesweeper: &mut Minesweeper<R>,
+        time: Duration,
+    ) -> Result<(), R::Error> {
+        while let Some(event) = self.replay.events.get(self.position) {
+            if event.time > time {
+                break;
+            }
+            self.position += 1;
+
+            match event.event {
+                ReplayEvent::PointerMoved(point) => minesweeper.on_pointer_moved(&point)?,
+                ReplayEvent::ParentSizeChanged(size) => {
+                    minesweeper.on_parent_size_changed(&size)?
+                }
+                ReplayEvent::TileSelected(tile) => minesweeper.select_tile(tile)?,
+                ReplayEvent::ChordPressed => minesweeper.on_chord_pressed()?,
+                ReplayEvent::ChordReleased => minesweeper.on_chord_released()?,
+                ReplayEvent::Command(command) => minesweeper.execute_unlocked(command)?,
+            }
+        }
+        self.time = time;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameStatus;
+    use crate::renderer::RecordingRenderer;
+    use crate::tile::MineState;
+
+    fn minesweeper() -> Minesweeper<RecordingRenderer> {
+        Minesweeper::new(
+            RecordingRenderer::new(),
+            &Size::new(100.0, 100.0),
+            GameConfig::default(),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn save_text(replay: &Replay) -> String {
+        let mut bytes = Vec::new();
+        replay.save(&mut bytes).unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    fn load(text: &str) -> Result<Replay, FormatError> {
+        Replay::load(text.as_bytes())
+    }
+
+    fn tile(x: i32, y: i32) -> TileCoordinate {
+        TileCoordinate { x, y }
+    }
+
+    fn seconds(seconds: f64) -> Duration {
+        Duration::from_secs_f64(seconds)
+    }
+
+    /// A replay with one input every second: a selection, a reveal, another
+    /// selection and a mark.
+    fn timed_replay() -> Replay {
+        let mut replay = Replay::new(GameConfig::new(9, 9, 10).unwrap(), 5);
+        replay.push(
+            seconds(0.0),
+            ReplayEvent::ParentSizeChanged(Size::new(9.0, 9.0)),
+        );
+        replay.push(seconds(1.0), ReplayEvent::TileSelected(tile(4, 4)));
+        replay.push(seconds(2.0), ReplayEvent::Command(Command::Reveal));
+        replay.push(
+            seconds(3.0),
+            ReplayEvent::PointerMoved(Point::new(0.5, 8.5)),
+        );
+        replay.push(seconds(4.0), ReplayEvent::Command(Command::Mark));
+        replay
+    }
+
+    /// Plays the whole of `replay` on a new game.
+    fn play(replay: &Replay) -> Minesweeper<RecordingRenderer> {
+        let mut minesweeper = minesweeper();
+        let mut player = ReplayPlayer::new(replay.clone());
+        player.start(&mut minesweeper).unwrap();
+        player.seek(&mut minesweeper, replay.duration()).unwrap();
+        assert!(player.is_finished());
+        minesweeper
+    }
+
+    /// A seeded practice game that is lost on its second reveal and then
+    /// undone.
+    fn lost_and_undone() -> Minesweeper<RecordingRenderer> {
+        let mut minesweeper = minesweeper();
+        let config = GameConfig::new(9, 9, 10).unwrap().with_practice(true);
+        minesweeper.new_game(config).unwrap();
+        minesweeper.new_game_with_seed(1).unwrap();
+        minesweeper
+            .select_tile(TileCoordinate { x: 4, y: 4 })
+            .unwrap();
+        minesweeper.execute(Command::Reveal).unwrap();
+
+        let index_helper = *minesweeper.game().index_helper();
+        let mine = (0..81)
+            .find(|&index| minesweeper.game().is_mine(index))
+            .unwrap();
+        minesweeper
+            .select_tile(TileCoordinate {
+                x: index_helper.compute_x_from_index(mine),
+                y: index_helper.compute_y_from_index(mine),
+            })
+            .unwrap();
+        minesweeper.execute(Command::Reveal).unwrap();
+        assert!(matches!(minesweeper.game().status(), GameStatus::Lost(_)));
+        minesweeper.execute(Command::Undo).unwrap();
+        minesweeper
+    }
+
+    #[test]
+    fn playback_runs_commands_while_an_animation_plays() {
+        let original = lost_and_undone();
+        let replay = original.replay().unwrap().clone();
+
+        // The real renderers keep animating on the clock, however fast the
+        // replay is played.
+        let mut minesweeper = minesweeper();
+        minesweeper.ui_mut().set_animation_playing(true);
+        let mut player = ReplayPlayer::new(replay.clone());
+        player.start(&mut minesweeper).unwrap();
+        player.seek(&mut minesweeper, replay.duration()).unwrap();
+
+        assert!(player.is_finished());
+        assert_eq!(minesweeper.game().status(), GameStatus::Playing);
+        assert_eq!(
+            minesweeper.game().mine_states(),
+            original.game().mine_states()
+        );
+        assert_eq!(minesweeper.game().undos_used(), 1);
+    }
+
+    #[test]
+    fn replays_round_trip() {
+        let mut replay = lost_and_undone().replay().unwrap().clone();
+        replay.push(
+            replay.duration(),
+            ReplayEvent::PointerMoved(Point::new(1.25, -0.1)),
+        );
+        replay.push(replay.duration(), ReplayEvent::ChordPressed);
+        replay.push(replay.duration(), ReplayEvent::ChordReleased);
+        replay.push(replay.duration(), ReplayEvent::Command(Command::Hint));
+
+        let text = save_text(&replay);
+        let loaded = load(&text).unwrap();
+        assert_eq!(loaded, replay);
+        assert_eq!(save_text(&loaded), text);
+    }
+
+    #[test]
+    fn playback_ends_on_the_board_of_the_original_game() {
+        let original = lost_and_undone();
+        let replay = load(&save_text(original.replay().unwrap())).unwrap();
+        let minesweeper = play(&replay);
+        assert_eq!(minesweeper.seed(), original.seed());
+        assert_eq!(minesweeper.game().status(), original.game().status());
+        assert_eq!(
+            minesweeper.game().mine_states(),
+            original.game().mine_states()
+        );
+    }
+
+    #[test]
+    fn advance_plays_the_events_that_are_due() {
+        let mut minesweeper = minesweeper();
+        let mut player = ReplayPlayer::new(timed_replay());
+        player.start(&mut minesweeper).unwrap();
+
+        player.advance(&mut minesweeper, seconds(1.5)).unwrap();
+        assert_eq!(minesweeper.current_tile(), Some(tile(4, 4)));
+        assert!(!minesweeper.game().are_mines_generated());
+
+        player.advance(&mut minesweeper, seconds(0.5)).unwrap();
+        assert_eq!(player.time(), seconds(2.0));
+        assert!(minesweeper.game().are_mines_generated());
+        assert!(!player.is_finished());
+    }
+
+    #[test]
+    fn paused_playback_stands_still() {
+        let mut minesweeper = minesweeper();
+        let mut player = ReplayPlayer::new(timed_replay());
+        player.start(&mut minesweeper).unwrap();
+
+        player.set_paused(true);
+        player.advance(&mut minesweeper, seconds(10.0)).unwrap();
+        assert_eq!(player.time(), seconds(0.0));
+        assert!(player.is_paused());
+        assert_eq!(minesweeper.current_tile(), None);
+
+        player.set_paused(false);
+        player.advance(&mut minesweeper, seconds(10.0)).unwrap();
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn speed_scales_real_time() {
+        let mut minesweeper = minesweeper();
+        let mut player = ReplayPlayer::new(timed_replay());
+        player.start(&mut minesweeper).unwrap();
+
+        player.set_speed(4.0);
+        player.advance(&mut minesweeper, seconds(0.5)).unwrap();
+        assert_eq!(player.time(), seconds(2.0));
+        assert!(minesweeper.game().are_mines_generated());
+
+        // Time cannot run backwards.
+        player.set_speed(-1.0);
+        assert_eq!(player.speed(), 0.0);
+        player.advance(&mut minesweeper, seconds(1.0)).unwrap();
+        assert_eq!(player.time(), seconds(2.0));
+    }
+
+    #[test]
+    fn seeking_back_plays_the_game_again() {
+        let replay = timed_replay();
+        let finished = play(&replay);
+        assert_eq!(finished.game().mine_state(0, 8), MineState::Flag);
+
+        let mut minesweeper = minesweeper();
+        let mut player = ReplayPlayer::new(replay);
+        player.start(&mut minesweeper).unwrap();
+        player.seek(&mut minesweeper, seconds(10.0)).unwrap();
+        assert_eq!(minesweeper.game().mine_state(0, 8), MineState::Flag);
+
+        player.seek(&mut minesweeper, seconds(1.5)).unwrap();
+        assert_eq!(player.time(), seconds(1.5));
+        assert!(!player.is_finished());
+        assert!(!minesweeper.game().are_mines_generated());
+        assert_eq!(minesweeper.game().mine_state(0, 8), MineState::Empty);
+
+        player.seek(&mut minesweeper, seconds(4.0)).unwrap();
+        assert_eq!(
+            minesweeper.game().mine_states(),
+            finished.game().mine_states()
+        );
+    }
+
+    #[test]
+    fn invalid_events_are_rejected() {
+        let text = save_text(&timed_replay());
+        for (from, to) in [
+            ("2000000000 reveal", "2000000000 fly"),
+            ("2000000000 reveal", "2000000000 reveal 1"),
+            ("2000000000 reveal", "2000000000 restart"),
+            ("3000000000 move", "500000000 move"),
+            ("4000000000 mark\n", ""),
+        ] {
+            assert!(text.contains(from));
+            assert!(load(&text.replace(from, to)).is_err(), "{}", to);
+        }
+        assert!(matches!(
+            load(&text.replace("minesweeper-replay", "minesweeper-save")),
+            Err(FormatError::UnknownFormat)
+        ));
+    }
+}
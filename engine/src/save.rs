@@ -0,0 +1,371 @@
+use crate::file_format::{
+    read_config, read_header, write_config, write_header, FormatError, LineReader,
+};
+use crate::game::{Game, GameState, GameStatus};
+use crate::generation::GenerationOutcome;
+use crate::tile::{MineState, TileCoordinate};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+const SAVE_HEADER: &str = "minesweeper-save";
+const SAVE_VERSION: u32 = 1;
+
+/// A game in progress, written to disk so that it can be resumed later.
+///
+/// The board is stored one row per line. Mines are written as `*` and other
+/// tiles as their number; covered tiles are written as `#`, flags as `F`,
+/// question marks as `?` and revealed tiles as `.`.
+pub struct SavedGame {
+    pub game: Game,
+    /// How long the game had been played when it was saved.
+    pub elapsed: Duration,
+}
+
+impl SavedGame {
+    pub fn new(game: Game, elapsed: Duration) -> Self {
+        Self { game, elapsed }
+    }
+
+    pub fn save<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_game(writer, &self.game, self.elapsed)
+    }
+
+    /// Reads a saved game. Files from other versions, and files whose board
+    /// does not add up, are rejected.
+    pub fn load<R: BufRead>(reader: R) -> Result<Self, FormatError> {
+        let mut reader = LineReader::new(reader);
+        read_header(&mut reader, SAVE_HEADER, SAVE_VERSION)?;
+        let config = read_config(&mut reader)?;
+        let seed = reader.expect_value("seed")?;
+        let elapsed = Duration::from_nanos(reader.expect_value("elapsed")?);
+
+        let status = reader.expect("status")?;
+        let status = match status.first().map(String::as_str) {
+            Some("playing") if status.len() == 1 => GameStatus::Playing,
+            Some("won") if status.len() == 1 => GameStatus::Won,
+            Some("lost") if status.len() == 3 => GameStatus::Lost(TileCoordinate {
+                x: reader.parse(&status[1])?,
+                y: reader.parse(&status[2])?,
+            }),
+            _ => return Err(reader.invalid("invalid status".to_string())),
+        };
+        let hints_used = reader.expect_value("hints")?;
+        let undos_used = reader.expect_value("undos")?;
+
+        let width = config.width() as usize;
+        let height = config.height() as usize;
+        let mut mines = vec![false; width * height];
+        let mut neighbor_counts = None;
+        let generation = reader.expect("mines")?;
+        let outcome = match generation.first().map(String::as_str) {
+            Some("deferred") if generation.len() == 1 => None,
+            Some("random") if generation.len() == 1 => Some(GenerationOutcome::Random),
+            Some("no-guess") if generation.len() == 2 => Some(GenerationOutcome::NoGuess {
+                attempts: reader.parse(&generation[1])?,
+            }),
+            Some("budget-exhausted") if generation.len() == 2 => {
+                Some(GenerationOutcome::BudgetExhausted {
+                    attempts: reader.parse(&generation[1])?,
+                })
+            }
+            _ => return Err(reader.invalid("invalid mine generation".to_string())),
+        };
+        if outcome.is_some() {
+            let mut counts = vec![0; width * height];
+            let rows = read_rows(&mut reader, width, height)?;
+            for (y, row) in rows.iter().enumerate() {
+                for (x, tile) in row.chars().enumerate() {
+                    let index = x * height + y;
+                    counts[index] = match tile {
+                        '*' => {
+                            mines[index] = true;
+                            -1
+                        }
+                        '0'..='8' => tile.to_digit(10).unwrap() as i32,
+                        _ => return Err(reader.invalid(format!("invalid tile '{}'", tile))),
+                    };
+                }
+            }
+            neighbor_counts = Some(counts);
+        }
+
+        let mut mine_states = vec![MineState::Empty; width * height];
+        let tiles = reader.expect("tiles")?;
+        reader.expect_count(&tiles, 0)?;
+        let rows = read_rows(&mut reader, width, height)?;
+        for (y, row) in rows.iter().enumerate() {
+            for (x, tile) in row.chars().enumerate() {
+                mine_states[x * height + y] = match tile {
+                    '#' => MineState::Empty,
+                    'F' => MineState::Flag,
+                    '?' => MineState::Question,
+                    '.' => MineState::Revealed,
+                    _ => return Err(reader.invalid(format!("invalid tile '{}'", tile))),
+                };
+            }
+        }
+
+        let game = Game::from_state(GameState {
+            config,
+            seed,
+            mines: outcome.map(|outcome| (mines, outcome)),
+            neighbor_counts,
+            mine_states,
+            status,
+            hints_used,
+            undos_used,
+        })
+        .map_err(FormatError::InvalidState)?;
+
+        Ok(Self::new(game, elapsed))
+    }
+
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.save(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, FormatError> {
+        Self::load(BufReader::new(File::open(path)?))
+    }
+}
+
+/// Writes `game` in the format read by `SavedGame::load`. This lets a game be
+/// saved without giving it up.
+pub(crate) fn write_game<W: Write>(
+    writer: &mut W,
+    game: &Game,
+    elapsed: Duration,
+) -> io::Result<()> {
+    write_header(writer, SAVE_HEADER, SAVE_VERSION)?;
+    write_config(writer, &game.config())?;
+    writeln!(writer, "seed {}", game.seed())?;
+    writeln!(writer, "elapsed {}", elapsed.as_nanos())?;
+    match game.status() {
+        GameStatus::Playing => writeln!(writer, "status playing")?,
+        GameStatus::Won => writeln!(writer, "status won")?,
+        GameStatus::Lost(tile) => writeln!(writer, "status lost {} {}", tile.x, tile.y)?,
+    }
+    writeln!(writer, "hints {}", game.hints_used())?;
+    writeln!(writer, "undos {}", game.undos_used())?;
+
+    let index_helper = game.index_helper();
+    match game.generation_outcome() {
+        None => writeln!(writer, "mines deferred")?,
+        Some(outcome) => {
+            match outcome {
+                GenerationOutcome::Random => writeln!(writer, "mines random")?,
+                GenerationOutcome::NoGuess { attempts } => {
+                    writeln!(writer, "mines no-guess {}", attempts)?
+                }
+                GenerationOutcome::BudgetExhausted { attempts } => {
+                    writeln!(writer, "mines budget-exhausted {}", attempts)?
+                }
+            }
+            for y in 0..game.height() {
+                for x in 0..game.width() {
+                    let count = game.neighbor_count(index_helper.compute_index(x, y));
+                    if count < 0 {
+                        write!(writer, "*")?;
+                    } else {
+                        write!(writer, "{}", count)?;
+                    }
+                }
+                writeln!(writer)?;
+            }
+        }
+    }
+
+    writeln!(writer, "tiles")?;
+    for y in 0..game.height() {
+        for x in 0..game.width() {
+            let tile = match game.mine_state(x, y) {
+                MineState::Empty => '#',
+                MineState::Flag => 'F',
+                MineState::Question => '?',
+                MineState::Revealed => '.',
+            };
+            write!(writer, "{}", tile)?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Reads `height` lines of exactly `width` characters each.
+fn read_rows<R: BufRead>(
+    reader: &mut LineReader<R>,
+    width: usize,
+    height: usize,
+) -> Result<Vec<String>, FormatError> {
+    let mut rows = Vec::with_capacity(height);
+    for _ in 0..height {
+        let fields = reader.next_fields()?.ok_or(FormatError::UnexpectedEnd)?;
+        reader.expect_count(&fields, 1)?;
+        let row = &fields[0];
+        if row.chars().count() != width {
+            return Err(reader.invalid(format!(
+                "expected {} tiles, found {}",
+                width,
+                row.chars().count()
+            )));
+        }
+        rows.push(row.clone());
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigError, GameConfig, StartMode};
+    use crate::difficulty::Difficulty;
+    use crate::notation::{parse_board, print_board};
+    use crate::tile::MarkCycle;
+
+    fn save_text(game: &Game, elapsed: Duration) -> String {
+        let mut bytes = Vec::new();
+        write_game(&mut bytes, game, elapsed).unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    fn load(text: &str) -> Result<SavedGame, FormatError> {
+        SavedGame::load(text.as_bytes())
+    }
+
+    /// A game in progress with every setting changed from its default.
+    fn game_in_progress() -> Game {
+        let config = Difficulty::Intermediate
+            .config()
+            .unwrap()
+            .with_start_mode(StartMode::Opening { radius: 1 })
+            .unwrap()
+            .with_practice(true)
+            .with_mark_cycle(MarkCycle::QuestionFlag);
+        let mut game = Game::with_seed(config, 4321);
+        game.reveal(8, 8);
+        game.cycle_mark(0, 0);
+        game.cycle_mark(15, 15);
+        game.cycle_mark(15, 15);
+        game.hint();
+        game
+    }
+
+    #[test]
+    fn games_round_trip() {
+        let game = game_in_progress();
+        let text = save_text(&game, Duration::from_millis(12_345));
+        let saved = load(&text).unwrap();
+
+        assert_eq!(saved.elapsed, Duration::from_millis(12_345));
+        assert_eq!(saved.game.config(), game.config());
+        assert_eq!(saved.game.seed(), 4321);
+        assert_eq!(saved.game.status(), GameStatus::Playing);
+        assert_eq!(saved.game.hints_used(), 1);
+        assert_eq!(saved.game.generation_outcome(), game.generation_outcome());
+        assert_eq!(print_board(&saved.game), print_board(&game));
+        assert_eq!(save_text(&saved.game, saved.elapsed), text);
+    }
+
+    #[test]
+    fn finished_games_round_trip() {
+        for board in ["2X\n*.\n", "*1\n11\n", "1F1..\n111.*\n..q.f\n"] {
+            let game = parse_board(board).unwrap();
+            let saved = load(&save_text(&game, Duration::from_secs(1))).unwrap();
+            assert_eq!(saved.game.status(), game.status());
+            assert_eq!(print_board(&saved.game).as_deref(), Some(board));
+        }
+    }
+
+    #[test]
+    fn games_before_the_first_reveal_keep_their_seed() {
+        let config = Difficulty::Beginner.config().unwrap();
+        let mut game = Game::with_seed(config, 77);
+        game.cycle_mark(2, 3);
+        let mut saved = load(&save_text(&game, Duration::from_secs(0))).unwrap();
+        assert!(!saved.game.are_mines_generated());
+        assert_eq!(saved.game.mine_state(2, 3), MineState::Flag);
+
+        game.reveal(4, 4);
+        saved.game.reveal(4, 4);
+        assert_eq!(print_board(&saved.game), print_board(&game));
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let text = save_text(&game_in_progress(), Duration::from_secs(3));
+        for version in [0, 2, 100] {
+            let text = text.replace(
+                "minesweeper-save 1",
+                &format!("minesweeper-save {}", version),
+            );
+            assert!(matches!(
+                load(&text),
+                Err(FormatError::UnsupportedVersion(found)) if found == version
+            ));
+        }
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        for text in [
+            "",
+            "hello world",
+            "minesweeper-replay 1\n",
+            "minesweeper-save two\n",
+        ] {
+            assert!(matches!(load(text), Err(FormatError::UnknownFormat)));
+        }
+        assert!(matches!(
+            SavedGame::load(&[0xff, 0xfe, 0x00, 0x80][..]),
+            Err(FormatError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn empty_boards_are_rejected() {
+        let game = Game::with_seed(GameConfig::new(3, 3, 1).unwrap(), 1);
+        let text = save_text(&game, Duration::from_secs(0)).replace("size 3 3 1", "size 0 0 0");
+        assert!(matches!(
+            load(&text),
+            Err(FormatError::InvalidConfig(ConfigError::InvalidDimensions {
+                width: 0,
+                height: 0
+            }))
+        ));
+    }
+
+    #[test]
+    fn truncated_files_are_rejected() {
+        let text = save_text(&game_in_progress(), Duration::from_secs(3));
+        let lines: Vec<&str> = text.lines().collect();
+        for count in 1..lines.len() {
+            let truncated = lines[..count].join("\n");
+            assert!(load(&truncated).is_err(), "{} lines were accepted", count);
+        }
+
+        // A row cut short is pointed out.
+        let cut = &text[..text.len() - 5];
+        match load(cut) {
+            Err(FormatError::InvalidLine { message, .. }) => {
+                assert_eq!(message, "expected 16 tiles, found 12")
+            }
+            _ => panic!("a cut row was accepted"),
+        }
+    }
+
+    #[test]
+    fn inconsistent_boards_are_rejected() {
+        let game = parse_board("1.\n*.").unwrap();
+        let text = save_text(&game, Duration::from_secs(0));
+        assert!(text.contains("mines random\n11\n*1\n"));
+        let text = text.replace("mines random\n11\n", "mines random\n12\n");
+        assert!(matches!(
+            load(&text),
+            Err(FormatError::InvalidState(message)) if message == "the numbers do not match the mines"
+        ));
+    }
+}
@@ -0,0 +1,553 @@
+use crate::game::Game;
+use crate::index_helper::IndexHelper;
+use crate::tile::{MineState, TileCoordinate};
+use std::fmt;
+
+/// What a player can see of a single tile.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum VisibleTile {
+    Covered,
+    /// A covered tile that is known to hold a mine.
+    Mine,
+    /// A revealed tile and the number of mines around it.
+    Revealed(i32),
+}
+
+/// The player's view of a board: revealed counts, known mines and covered
+/// tiles, plus the total number of mines. Uses `IndexHelper` indexing.
+#[derive(Clone, Debug)]
+pub struct VisibleBoard {
+    index_helper: IndexHelper,
+    tiles: Vec<VisibleTile>,
+    num_mines: i32,
+}
+
+impl VisibleBoard {
+    pub fn new(width: i32, height: i32, num_mines: i32) -> Self {
+        let index_helper = IndexHelper::new(width, height);
+        Self {
+            tiles: vec![VisibleTile::Covered; index_helper.tile_count()],
+            index_helper,
+            num_mines,
+        }
+    }
+
+    /// Builds the board a player of `game` sees. Mines are never read; only
+    /// the counts of revealed tiles are. Flags can be wrong, so they are only
+    /// treated as known mines when `trust_flags` is set. Question marks are
+    /// always treated as covered tiles.
+    pub fn from_game(game: &Game, trust_flags: bool) -> Self {
+        let mut board = Self::new(game.width(), game.height(), game.num_mines());
+        for (index, state) in game.mine_states().iter().enumerate() {
+            board.tiles[index] = match state {
+                MineState::Revealed => VisibleTile::Revealed(game.neighbor_count(index)),
+                MineState::Flag if trust_flags => VisibleTile::Mine,
+                _ => VisibleTile::Covered,
+            };
+        }
+        board
+    }
+
+    /// The board a player sees of `text`, written in the notation read by
+    /// `parse_board`.
+    #[cfg(test)]
+    pub(crate) fn from_notation(text: &str, trust_flags: bool) -> Self {
+        Self::from_game(&crate::notation::parse_board(text).unwrap(), trust_flags)
+    }
+
+    pub fn index_helper(&self) -> &IndexHelper {
+        &self.index_helper
+    }
+
+    pub fn num_mines(&self) -> i32 {
+        self.num_mines
+    }
+
+    pub fn tiles(&self) -> &[VisibleTile] {
+        &self.tiles
+    }
+
+    pub fn tile(&self, index: usize) -> VisibleTile {
+        self.tiles[index]
+    }
+
+    pub fn set_tile(&mut self, index: usize, tile: VisibleTile) {
+        self.tiles[index] = tile;
+    }
+
+    /// Returns the number of covered tiles that are not known mines.
+    pub fn covered_count(&self) -> usize {
+        self.tiles
+            .iter()
+            .filter(|tile| **tile == VisibleTile::Covered)
+            .count()
+    }
+
+    /// Returns the number of mines that are not known yet.
+    pub fn remaining_mines(&self) -> i32 {
+        let known_mines = self
+            .tiles
+            .iter()
+            .filter(|tile| **tile == VisibleTile::Mine)
+            .count() as i32;
+        self.num_mines - known_mines
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Verdict {
+    Safe,
+    Mine,
+}
+
+/// A revealed number that took part in a deduction.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Clue {
+    pub tile: TileCoordinate,
+    pub count: i32,
+}
+
+impl fmt::Display for Clue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the {} at ({}, {})",
+            self.count, self.tile.x, self.tile.y
+        )
+    }
+}
+
+/// Why a deduction holds.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Reason {
+    /// The clue alone settles all of its covered neighbors: either it already
+    /// touches as many known mines as it shows, or it has exactly as many
+    /// covered neighbors as it is missing mines.
+    SingleCell(Clue),
+    /// Every covered neighbor of `inner` also borders `outer`, so the other
+    /// covered neighbors of `outer` hold exactly the difference of their
+    /// remaining mine counts.
+    Subset { inner: Clue, outer: Clue },
+    /// Two clues share some covered neighbors, and `second` needs more mines
+    /// than the shared tiles can hold. The classic 1-2 pattern.
+    Overlap { first: Clue, second: Clue },
+    /// The mine counter: every remaining mine is accounted for, or every
+    /// covered tile must be a mine.
+    MineCount { remaining: i32 },
+}
+
+/// A tile whose contents follow from the visible board, and why.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Deduction {
+    pub tile: TileCoordinate,
+    pub index: usize,
+    pub verdict: Verdict,
+    pub reason: Reason,
+}
+
+impl fmt::Display for Deduction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verdict = match self.verdict {
+            Verdict::Safe => "safe",
+            Verdict::Mine => "a mine",
+        };
+        write!(f, "({}, {}) is {}: ", self.tile.x, self.tile.y, verdict)?;
+        match (self.reason, self.verdict) {
+            (Reason::SingleCell(clue), Verdict::Safe) => {
+                write!(f, "{} already touches all of its mines", clue)
+            }
+            (Reason::SingleCell(clue), Verdict::Mine) => {
+                write!(f, "{} has no other covered neighbors left", clue)
+            }
+            (Reason::Subset { inner, outer }, _) => write!(
+                f,
+                "every covered neighbor of {} also borders {}",
+                inner, outer
+            ),
+            (Reason::Overlap { first, second }, _) => write!(
+                f,
+                "{} and {} form a {}-{} pattern",
+                first, second, first.count, second.count
+            ),
+            (Reason::MineCount { remaining }, Verdict::Safe) => {
+                write!(f, "all mines are accounted for ({} left)", remaining)
+            }
+            (Reason::MineCount { remaining }, Verdict::Mine) => write!(
+                f,
+                "only {} covered tiles remain for {} mines",
+                remaining, remaining
+            ),
+        }
+    }
+}
+
+/// The covered tiles around a revealed number and how many of them are mines.
+struct Constraint {
+    clue: Clue,
+    cells: Vec<usize>,
+    mines: i32,
+}
+
+/// Finds the tiles that one pass of four rules settles: the single-cell rule
+/// on each number, the subset rule and the overlap pattern on each pair of
+/// numbers, and the mine count. The rules are not applied to each other's
+/// results, so deductions that need a chain of numbers are missed; the
+/// probabilities from `mine_probabilities` still show those tiles as 0.0 or
+/// 1.0. Only `board` is consulted, so the result never depends on hidden
+/// mines. Each tile is reported once, with the first rule that settled it.
+pub fn deduce(board: &VisibleBoard) -> Vec<Deduction> {
+    let index_helper = board.index_helper();
+    let constraints = build_constraints(board);
+    let mut result = DeductionSet::new(*index_helper);
+
+    // Single-cell rule
+    for constraint in constraints.iter().flatten() {
+        let reason = Reason::SingleCell(constraint.clue);
+        if constraint.mines == 0 {
+            result.add(&constraint.cells, Verdict::Safe, reason);
+        } else if constraint.mines == constraint.cells.len() as i32 {
+            result.add(&constraint.cells, Verdict::Mine, reason);
+        }
+    }
+
+    // Pairs of nearby clues that share covered neighbors
+    for (index, constraint) in constraints.iter().enumerate() {
+        let constraint = match constraint {
+            Some(constraint) => constraint,
+            None => continue,
+        };
+        let x = index_helper.compute_x_from_index(index);
+        let y = index_helper.compute_y_from_index(index);
+        for other_x in (x - 2)..=(x + 2) {
+            for other_y in (y - 2)..=(y + 2) {
+                if (other_x == x && other_y == y) || !index_helper.is_in_bounds(other_x, other_y) {
+                    continue;
+                }
+                if let Some(other) = &constraints[index_helper.compute_index(other_x, other_y)] {
+                    deduce_from_pair(constraint, other, &mut result);
+                }
+            }
+        }
+    }
+
+    // Global rule: the mine counter can settle every covered tile at once.
+    let covered: Vec<usize> = (0..board.tiles.len())
+        .filter(|index| board.tiles[*index] == VisibleTile::Covered)
+        .collect();
+    let remaining = board.remaining_mines();
+    if !covered.is_empty() {
+        let reason = Reason::MineCount { remaining };
+        if remaining == 0 {
+            result.add(&covered, Verdict::Safe, reason);
+        } else if remaining == covered.len() as i32 {
+            result.add(&covered, Verdict::Mine, reason);
+        }
+    }
+
+    result.into_deductions()
+}
+
+/// Returns one constraint per revealed number that still touches covered
+/// tiles, stored at the index of the number so that nearby ones can be found.
+fn build_constraints(board: &VisibleBoard) -> Vec<Option<Constraint>> {
+    let index_helper = board.index_helper();
+    let mut constraints = Vec::with_capacity(board.tiles.len());
+    for (index, tile) in board.tiles.iter().enumerate() {
+        constraints.push(match tile {
+            VisibleTile::Revealed(count) => {
+                let x = index_helper.compute_x_from_index(index);
+                let y = index_helper.compute_y_from_index(index);
+                let neighbors = index_helper.neighbor_indices(x, y);
+                let known_mines = neighbors
+                    .iter()
+                    .filter(|neighbor| board.tiles[**neighbor] == VisibleTile::Mine)
+                    .count() as i32;
+                let cells: Vec<usize> = neighbors
+                    .into_iter()
+                    .filter(|neighbor| board.tiles[*neighbor] == VisibleTile::Covered)
+                    .collect();
+                if cells.is_empty() {
+                    None
+                } else {
+                    Some(Constraint {
+                        clue: Clue {
+                            tile: TileCoordinate { x, y },
+                            count: *count,
+                        },
+                        cells,
+                        mines: count - known_mines,
+                    })
+                }
+            }
+            _ => None,
+        });
+    }
+    constraints
+}
+
+fn deduce_from_pair(first: &Constraint, second: &Constraint, result: &mut DeductionSet) {
+    let only_first: Vec<usize> = first
+        .cells
+        .iter()
+        .copied()
+        .filter(|cell| !second.cells.contains(cell))
+        .collect();
+    let only_second: Vec<usize> = second
+        .cells
+        .iter()
+        .copied()
+        .filter(|cell| !first.cells.contains(cell))
+        .collect();
+    let shared_count = (first.cells.len() - only_first.len()) as i32;
+    if shared_count == 0 || only_second.is_empty() {
+        return;
+    }
+
+    if only_first.is_empty() {
+        // Subset rule: the rest of `second` holds exactly the difference.
+        let reason = Reason::Subset {
+            inner: first.clue,
+            outer: second.clue,
+        };
+        let difference_mines = second.mines - first.mines;
+        if difference_mines == 0 {
+            result.add(&only_second, Verdict::Safe, reason);
+        } else if difference_mines == only_second.len() as i32 {
+            result.add(&only_second, Verdict::Mine, reason);
+        }
+        return;
+    }
+
+    // Overlap rule: the shared tiles hold at most this many of second's
+    // mines, so if the rest of second needs all of its tiles, they are mines
+    // and the shared tiles hold exactly what is left over.
+    let max_shared_mines = shared_count.min(first.mines);
+    if second.mines - max_shared_mines != only_second.len() as i32 {
+        return;
+    }
+
+    let reason = Reason::Overlap {
+        first: first.clue,
+        second: second.clue,
+    };
+    result.add(&only_second, Verdict::Mine, reason);
+
+    let shared_mines = second.mines - only_second.len() as i32;
+    let only_first_mines = first.mines - shared_mines;
+    if only_first_mines == 0 {
+        result.add(&only_first, Verdict::Safe, reason);
+    } else if only_first_mines == only_first.len() as i32 {
+        result.add(&only_first, Verdict::Mine, reason);
+    }
+}
+
+/// Collects deductions without repeating a tile.
+struct DeductionSet {
+    index_helper: IndexHelper,
+    seen: Vec<bool>,
+    deductions: Vec<Deduction>,
+}
+
+impl DeductionSet {
+    fn new(index_helper: IndexHelper) -> Self {
+        Self {
+            index_helper,
+            seen: vec![false; index_helper.tile_count()],
+            deductions: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, cells: &[usize], verdict: Verdict, reason: Reason) {
+        for cell in cells {
+            if !self.seen[*cell] {
+                self.seen[*cell] = true;
+                self.deductions.push(Deduction {
+                    tile: TileCoordinate {
+                        x: self.index_helper.compute_x_from_index(*cell),
+                        y: self.index_helper.compute_y_from_index(*cell),
+                    },
+                    index: *cell,
+                    verdict,
+                    reason,
+                });
+            }
+        }
+    }
+
+    fn into_deductions(self) -> Vec<Deduction> {
+        self.deductions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clue(x: i32, y: i32, count: i32) -> Clue {
+        Clue {
+            tile: TileCoordinate { x, y },
+            count,
+        }
+    }
+
+    /// The deductions on a board with the given height, in the order they
+    /// are found.
+    fn deduction(x: i32, y: i32, height: i32, verdict: Verdict, reason: Reason) -> Deduction {
+        Deduction {
+            tile: TileCoordinate { x, y },
+            index: (x * height + y) as usize,
+            verdict,
+            reason,
+        }
+    }
+
+    #[test]
+    fn one_two_one_finds_both_mines() {
+        let deductions = deduce(&VisibleBoard::from_notation("*.*\n121", false));
+        assert_eq!(
+            deductions,
+            [
+                deduction(
+                    2,
+                    0,
+                    2,
+                    Verdict::Mine,
+                    Reason::Subset {
+                        inner: clue(0, 1, 1),
+                        outer: clue(1, 1, 2),
+                    }
+                ),
+                deduction(
+                    0,
+                    0,
+                    2,
+                    Verdict::Mine,
+                    Reason::Subset {
+                        inner: clue(2, 1, 1),
+                        outer: clue(1, 1, 2),
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn one_two_one_with_flagged_mines_frees_the_middle() {
+        let deductions = deduce(&VisibleBoard::from_notation("F.F\n121", true));
+        assert_eq!(
+            deductions,
+            [deduction(
+                1,
+                0,
+                2,
+                Verdict::Safe,
+                Reason::SingleCell(clue(0, 1, 1))
+            )]
+        );
+    }
+
+    #[test]
+    fn one_one_against_a_wall_frees_the_far_side() {
+        let deductions = deduce(&VisibleBoard::from_notation("*..\n11.", false));
+        let reason = Reason::Subset {
+            inner: clue(0, 1, 1),
+            outer: clue(1, 1, 1),
+        };
+        assert_eq!(
+            deductions,
+            [
+                deduction(2, 0, 2, Verdict::Safe, reason),
+                deduction(2, 1, 2, Verdict::Safe, reason),
+            ]
+        );
+    }
+
+    #[test]
+    fn one_two_overlap_settles_both_sides() {
+        let deductions = deduce(&VisibleBoard::from_notation(".*.*\n1121", false));
+        let overlap = Reason::Overlap {
+            first: clue(1, 1, 1),
+            second: clue(2, 1, 2),
+        };
+        assert_eq!(
+            deductions,
+            [
+                deduction(
+                    2,
+                    0,
+                    2,
+                    Verdict::Safe,
+                    Reason::Subset {
+                        inner: clue(0, 1, 1),
+                        outer: clue(1, 1, 1),
+                    }
+                ),
+                deduction(3, 0, 2, Verdict::Mine, overlap),
+                deduction(0, 0, 2, Verdict::Safe, overlap),
+                deduction(
+                    1,
+                    0,
+                    2,
+                    Verdict::Mine,
+                    Reason::Subset {
+                        inner: clue(3, 1, 1),
+                        outer: clue(2, 1, 2),
+                    }
+                ),
+            ]
+        );
+        assert_eq!(
+            deductions[1].to_string(),
+            "(3, 0) is a mine: the 1 at (1, 1) and the 2 at (2, 1) form a 1-2 pattern"
+        );
+    }
+
+    #[test]
+    fn flags_are_only_used_when_trusted() {
+        assert_eq!(deduce(&VisibleBoard::from_notation("F1\n..", false)), []);
+        let reason = Reason::SingleCell(clue(1, 0, 1));
+        assert_eq!(
+            deduce(&VisibleBoard::from_notation("F1\n..", true)),
+            [
+                deduction(0, 1, 2, Verdict::Safe, reason),
+                deduction(1, 1, 2, Verdict::Safe, reason),
+            ]
+        );
+    }
+
+    #[test]
+    fn mine_count_settles_the_rest() {
+        let reason = Reason::MineCount { remaining: 0 };
+        assert_eq!(
+            deduce(&VisibleBoard::from_notation("F.\n..", true)),
+            [
+                deduction(0, 1, 2, Verdict::Safe, reason),
+                deduction(1, 0, 2, Verdict::Safe, reason),
+                deduction(1, 1, 2, Verdict::Safe, reason),
+            ]
+        );
+
+        // Boards always leave a tile free, but the solver does not rely on it.
+        let reason = Reason::MineCount { remaining: 2 };
+        assert_eq!(
+            deduce(&VisibleBoard::new(2, 1, 2)),
+            [
+                deduction(0, 0, 1, Verdict::Mine, reason),
+                deduction(1, 0, 1, Verdict::Mine, reason),
+            ]
+        );
+    }
+
+    #[test]
+    fn guesses_have_no_deduction() {
+        assert_eq!(deduce(&VisibleBoard::from_notation("1.\n.*", false)), []);
+        assert_eq!(
+            deduce(&VisibleBoard::from_notation("..\n..\n.*", false)),
+            []
+        );
+        // Only mines follow from a 1-2-1 without flags.
+        assert!(deduce(&VisibleBoard::from_notation("*.*\n121", false))
+            .iter()
+            .all(|deduction| deduction.verdict == Verdict::Mine));
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TileCoordinate {
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum MineState {
+    Empty,
+    Flag,
+    Question,
+    Revealed,
+}
+
+/// The marks a covered tile goes through, in order, before it is empty
+/// again. Marks that are left out of the cycle never appear on the board.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum MarkCycle {
+    /// Empty, flag, question mark, empty.
+    #[default]
+    FlagQuestion,
+    /// Empty, flag, empty. Question marks are disabled.
+    FlagOnly,
+    /// Empty, question mark, flag, empty.
+    QuestionFlag,
+}
+
+impl MarkCycle {
+    /// The marks in the order they are cycled through after `Empty`.
+    pub fn marks(&self) -> &'static [MineState] {
+        match self {
+            MarkCycle::FlagQuestion => &[MineState::Flag, MineState::Question],
+            MarkCycle::FlagOnly => &[MineState::Flag],
+            MarkCycle::QuestionFlag => &[MineState::Question, MineState::Flag],
+        }
+    }
+
+    /// Whether a covered tile may be in `state`.
+    pub fn allows(&self, state: MineState) -> bool {
+        state == MineState::Empty || self.marks().contains(&state)
+    }
+
+    /// The mark that follows `state`. A mark outside of the cycle goes back
+    /// to `Empty`.
+    pub fn next(&self, state: MineState) -> MineState {
+        if state == MineState::Revealed {
+            unreachable!("We shouldn't be cycling a revealed tile!");
+        }
+        let marks = self.marks();
+        let next = match marks.iter().position(|mark| *mark == state) {
+            Some(position) => position + 1,
+            None if state == MineState::Empty => 0,
+            None => marks.len(),
+        };
+        marks.get(next).copied().unwrap_or(MineState::Empty)
+    }
+}
+
+impl fmt::Display for MarkCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkCycle::FlagQuestion => write!(f, "flag-question"),
+            MarkCycle::FlagOnly => write!(f, "flag"),
+            MarkCycle::QuestionFlag => write!(f, "question-flag"),
+        }
+    }
+}
+
+impl FromStr for MarkCycle {
+    type Err = ParseMarkCycleError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "flag-question" => Ok(MarkCycle::FlagQuestion),
+            "flag" => Ok(MarkCycle::FlagOnly),
+            "question-flag" => Ok(MarkCycle::QuestionFlag),
+            _ => Err(ParseMarkCycleError(value.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseMarkCycleError(String);
+
+impl fmt::Display for ParseMarkCycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid marks '{}', expected flag-question, flag or question-flag",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseMarkCycleError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The states `cycle` goes through from `Empty` until it is back there.
+    fn cycle(cycle: MarkCycle) -> Vec<MineState> {
+        let mut states = vec![MineState::Empty];
+        loop {
+            let next = cycle.next(*states.last().unwrap());
+            states.push(next);
+            if next == MineState::Empty {
+                return states;
+            }
+        }
+    }
+
+    #[test]
+    fn each_cycle_goes_through_its_marks_and_back() {
+        use MineState::{Empty, Flag, Question};
+        assert_eq!(
+            cycle(MarkCycle::FlagQuestion),
+            [Empty, Flag, Question, Empty]
+        );
+        assert_eq!(cycle(MarkCycle::FlagOnly), [Empty, Flag, Empty]);
+        assert_eq!(
+            cycle(MarkCycle::QuestionFlag),
+            [Empty, Question, Flag, Empty]
+        );
+    }
+
+    #[test]
+    fn marks_outside_of_the_cycle_are_cleared() {
+        assert_eq!(
+            MarkCycle::FlagOnly.next(MineState::Question),
+            MineState::Empty
+        );
+        assert!(!MarkCycle::FlagOnly.allows(MineState::Question));
+        assert!(MarkCycle::QuestionFlag.allows(MineState::Question));
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for marks in [
+            MarkCycle::FlagQuestion,
+            MarkCycle::FlagOnly,
+            MarkCycle::QuestionFlag,
+        ] {
+            assert_eq!(marks.to_string().parse(), Ok(marks));
+        }
+        assert!("question".parse::<MarkCycle>().is_err());
+    }
+}
@@ -0,0 +1,67 @@
+//! Draws a board into a PNG or SVG, for bug reports and thumbnails of shared
+//! seeds.
+//!
+//! ```text
+//! cargo run --release -p raster --bin snapshot -- --seed 1234 --reveal 15,8 board.png
+//! cargo run --release -p raster --bin snapshot -- --board position.txt --scale 2 board.svg
+//! ```
+//!
+//! `--board` reads a board in the text notation. Otherwise the board is
+//! generated from `--seed` and `--difficulty` and, since mines are only placed
+//! by the first reveal, `--reveal x,y` makes that first move.
+
+use engine::args::parse_arg;
+use engine::{parse_board, Difficulty, Game};
+use raster::{draw_board, save_svg, BoardScene, Layout};
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let output = std::env::args()
+        .skip(1)
+        .last()
+        .filter(|arg| arg.ends_with(".png") || arg.ends_with(".svg"))
+        .ok_or("The last argument must be the .png or .svg file to write")?;
+
+    let game = match parse_arg::<String>("--board")? {
+        Some(path) => {
+            let text = std::fs::read_to_string(&path).map_err(|error| error.to_string())?;
+            parse_board(&text).map_err(|error| format!("{}: {}", path, error))?
+        }
+        None => {
+            let difficulty = parse_arg::<Difficulty>("--difficulty")?.unwrap_or_default();
+            let config = difficulty.config().map_err(|error| error.to_string())?;
+            let mut game = Game::with_seed(config, parse_arg("--seed")?.unwrap_or(0));
+            if let Some(reveal) = parse_arg::<String>("--reveal")? {
+                let (x, y) = parse_coordinate(&reveal)
+                    .ok_or_else(|| format!("'{}' is not a tile, expected x,y", reveal))?;
+                game.reveal(x, y);
+            }
+            game
+        }
+    };
+
+    let layout = Layout::scaled(parse_arg("--scale")?.unwrap_or(1.0));
+    let scene = BoardScene::from_game(&game);
+    let result = if output.ends_with(".svg") {
+        save_svg(&scene, &layout, &output)
+    } else {
+        draw_board(&scene, &layout).save_png(&output)
+    };
+    result.map_err(|error| format!("Failed to write {}: {}", output, error))
+}
+
+fn parse_coordinate(value: &str) -> Option<(i32, i32)> {
+    let mut parts = value.split(',');
+    let x = parts.next()?.trim().parse().ok()?;
+    let y = parts.next()?.trim().parse().ok()?;
+    match parts.next() {
+        Some(_) => None,
+        None => Some((x, y)),
+    }
+}
@@ -0,0 +1,273 @@
+use crate::image::RgbaImage;
+use engine::palette::{self, Color};
+use engine::{Game, GameStatus, IndexHelper, MineState, TileCoordinate};
+
+/// What a tile currently shows.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TileView {
+    Covered(MineState),
+    Revealed(i32),
+    Mine,
+}
+
+/// Everything that is drawn for a board, independent of any game logic.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BoardScene {
+    index_helper: IndexHelper,
+    /// Uses `IndexHelper` indexing.
+    pub tiles: Vec<TileView>,
+    pub selection: Option<TileCoordinate>,
+    /// The probability overlay, with `None` for tiles that are not tinted.
+    pub probabilities: Option<Vec<Option<f64>>>,
+}
+
+impl BoardScene {
+    /// Creates a board with every tile covered.
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            index_helper: IndexHelper::new(width, height),
+            tiles: vec![TileView::Covered(MineState::Empty); (width * height) as usize],
+            selection: None,
+            probabilities: None,
+        }
+    }
+
+    /// Shows `game` the way a player sees it. A lost game shows every mine,
+    /// as it does once the loss animation has finished.
+    pub fn from_game(game: &Game) -> Self {
+        let mut scene = Self::new(game.width(), game.height());
+        let is_lost = matches!(game.status(), GameStatus::Lost(_));
+        for (index, state) in game.mine_states().iter().enumerate() {
+            let is_mine = game.is_mine(index);
+            scene.tiles[index] = match state {
+                _ if is_mine && (is_lost || *state == MineState::Revealed) => TileView::Mine,
+                MineState::Revealed => TileView::Revealed(game.neighbor_count(index)),
+                state => TileView::Covered(*state),
+            };
+        }
+        scene
+    }
+
+    pub fn index_helper(&self) -> &IndexHelper {
+        &self.index_helper
+    }
+
+    pub fn width(&self) -> i32 {
+        self.index_helper.width()
+    }
+
+    pub fn height(&self) -> i32 {
+        self.index_helper.height()
+    }
+
+    pub fn tile(&self, tile_coordinate: &TileCoordinate) -> TileView {
+        self.tiles[self
+            .index_helper
+            .compute_index(tile_coordinate.x, tile_coordinate.y)]
+    }
+
+    pub fn set_tile(&mut self, tile_coordinate: &TileCoordinate, view: TileView) {
+        let index = self
+            .index_helper
+            .compute_index(tile_coordinate.x, tile_coordinate.y);
+        self.tiles[index] = view;
+    }
+}
+
+/// Where tiles go in an image. Every tile is surrounded by half a margin, as
+/// in the composition front-end.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Layout {
+    pub tile_size: f32,
+    pub margin: f32,
+}
+
+impl Layout {
+    /// The layout at `scale` times the size the composition front-end uses.
+    pub fn scaled(scale: f32) -> Self {
+        Self {
+            tile_size: palette::TILE_SIZE * scale,
+            margin: palette::TILE_MARGIN * scale,
+        }
+    }
+
+    /// The size of a board with the given number of tiles.
+    pub fn board_size(&self, width: i32, height: i32) -> (f32, f32) {
+        let step = self.tile_size + self.margin;
+        (step * width as f32, step * height as f32)
+    }
+
+    /// The top left corner of a tile.
+    pub fn tile_origin(&self, tile_coordinate: &TileCoordinate) -> (f32, f32) {
+        let step = self.tile_size + self.margin;
+        (
+            self.margin / 2.0 + step * tile_coordinate.x as f32,
+            self.margin / 2.0 + step * tile_coordinate.y as f32,
+        )
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::scaled(1.0)
+    }
+}
+
+/// Draws `scene` into a new image that is just large enough for it. Pixels
+/// between the tiles are transparent.
+pub fn draw_board(scene: &BoardScene, layout: &Layout) -> RgbaImage {
+    let (width, height) = layout.board_size(scene.width(), scene.height());
+    let mut image = RgbaImage::new(width.ceil() as u32, height.ceil() as u32);
+    draw_board_into(&mut image, scene, layout, (0.0, 0.0));
+    image
+}
+
+/// Draws `scene` over `image`, with the top left corner of the board at
+/// `offset`.
+pub fn draw_board_into(
+    image: &mut RgbaImage,
+    scene: &BoardScene,
+    layout: &Layout,
+    offset: (f32, f32),
+) {
+    let tile_size = layout.tile_size;
+    for index in 0..scene.tiles.len() {
+        let tile_coordinate = TileCoordinate {
+            x: scene.index_helper.compute_x_from_index(index),
+            y: scene.index_helper.compute_y_from_index(index),
+        };
+        let (x, y) = layout.tile_origin(&tile_coordinate);
+        let (left, top) = (offset.0 + x, offset.1 + y);
+        let (right, bottom) = (left + tile_size, top + tile_size);
+
+        match scene.tiles[index] {
+            TileView::Covered(state) => {
+                image.fill_rect(left, top, right, bottom, palette::covered_color(state));
+                if let Some(Some(probability)) = scene
+                    .probabilities
+                    .as_ref()
+                    .map(|probabilities| probabilities[index])
+                {
+                    let tint = palette::probability_color(probability);
+                    image.fill_rect(left, top, right, bottom, tint);
+                }
+            }
+            TileView::Revealed(count) => {
+                image.fill_rect(left, top, right, bottom, palette::mine_count_color(count));
+                for (dot_x, dot_y) in palette::dot_positions(count) {
+                    image.fill_circle(
+                        left + dot_x * tile_size,
+                        top + dot_y * tile_size,
+                        palette::DOT_RADIUS * tile_size,
+                        palette::DOT_COLOR,
+                    );
+                }
+            }
+            TileView::Mine => image.fill_rect(left, top, right, bottom, palette::MINE_COLOR),
+        }
+    }
+
+    if let Some(selection) = scene.selection {
+        let (x, y) = layout.tile_origin(&selection);
+        let margin = layout.margin;
+        let (left, top) = (offset.0 + x - margin, offset.1 + y - margin);
+        let (right, bottom) = (
+            left + tile_size + margin * 2.0,
+            top + tile_size + margin * 2.0,
+        );
+        draw_frame(
+            image,
+            (left, top, right, bottom),
+            margin,
+            palette::SELECTION_COLOR,
+        );
+    }
+}
+
+/// Draws the outline of a rectangle, `thickness` wide, inside its edges.
+fn draw_frame(
+    image: &mut RgbaImage,
+    (left, top, right, bottom): (f32, f32, f32, f32),
+    thickness: f32,
+    color: Color,
+) {
+    image.fill_rect(left, top, right, top + thickness, color);
+    image.fill_rect(left, bottom - thickness, right, bottom, color);
+    image.fill_rect(
+        left,
+        top + thickness,
+        left + thickness,
+        bottom - thickness,
+        color,
+    );
+    image.fill_rect(
+        right - thickness,
+        top + thickness,
+        right,
+        bottom - thickness,
+        color,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine::parse_board;
+
+    fn draw(board: &str) -> RgbaImage {
+        let game = parse_board(board).unwrap();
+        draw_board(&BoardScene::from_game(&game), &Layout::default())
+    }
+
+    #[test]
+    fn tiles_are_drawn_in_their_palette_colors() {
+        let image = draw("1*\nf.");
+
+        assert_eq!((image.width(), image.height()), (55, 55));
+        assert_eq!(image.pixel(3, 3), palette::mine_count_color(1));
+        assert_eq!(image.pixel(13, 13), palette::DOT_COLOR);
+        assert_eq!(image.pixel(40, 3), palette::covered_color(MineState::Empty));
+        assert_eq!(image.pixel(3, 40), palette::covered_color(MineState::Flag));
+        assert_eq!(image.pixel(27, 3).a, 0);
+    }
+
+    #[test]
+    fn lost_boards_show_every_mine() {
+        let image = draw("XQ\n.2");
+
+        assert_eq!(image.pixel(3, 3), palette::MINE_COLOR);
+        assert_eq!(image.pixel(40, 3), palette::MINE_COLOR);
+        assert_eq!(image.pixel(3, 40), palette::covered_color(MineState::Empty));
+        assert_eq!(image.pixel(40, 40), palette::mine_count_color(2));
+    }
+
+    #[test]
+    fn the_selection_frame_fills_the_margin() {
+        let mut scene = BoardScene::new(2, 2);
+        scene.selection = Some(TileCoordinate { x: 1, y: 1 });
+        let image = draw_board(&scene, &Layout::default());
+
+        assert_eq!(image.pixel(27, 40), palette::SELECTION_COLOR);
+        assert_eq!(image.pixel(40, 27), palette::SELECTION_COLOR);
+        assert_eq!(image.pixel(27, 3).a, 0);
+        assert_eq!(
+            image.pixel(40, 40),
+            palette::covered_color(MineState::Empty)
+        );
+    }
+
+    #[test]
+    fn png_files_hold_the_drawn_pixels() {
+        let image = draw("1*\nf.");
+        let mut bytes = Vec::new();
+        image.write_png(&mut bytes).unwrap();
+
+        let (info, mut reader) = png::Decoder::new(&bytes[..]).read_info().unwrap();
+        let mut pixels = vec![0; info.buffer_size()];
+        reader.next_frame(&mut pixels).unwrap();
+
+        assert_eq!((info.width, info.height), (55, 55));
+        assert_eq!(info.color_type, png::ColorType::RGBA);
+        assert_eq!(pixels, image.pixels());
+    }
+}
@@ -0,0 +1,148 @@
+use engine::palette::Color;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// How many samples per axis `fill_circle` takes inside each pixel.
+const CIRCLE_SAMPLES: u32 = 4;
+
+/// An 8-bit RGBA image that is not premultiplied, stored row by row.
+#[derive(Clone, PartialEq, Debug)]
+pub struct RgbaImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    /// Creates a fully transparent image.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; (width * height * 4) as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The pixels as RGBA bytes, row by row from the top.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        let offset = self.offset(x, y);
+        let pixel = &self.pixels[offset..offset + 4];
+        Color::rgba(pixel[0], pixel[1], pixel[2], pixel[3])
+    }
+
+    /// Replaces every pixel with `color`.
+    pub fn clear(&mut self, color: Color) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[color.r, color.g, color.b, color.a]);
+        }
+    }
+
+    /// Draws a rectangle given by its edges. Pixels the edges only partly
+    /// cover are blended by how much of them is covered.
+    pub fn fill_rect(&mut self, left: f32, top: f32, right: f32, bottom: f32, color: Color) {
+        let (x_start, x_end) = self.pixel_span(left, right, self.width);
+        let (y_start, y_end) = self.pixel_span(top, bottom, self.height);
+        for y in y_start..y_end {
+            let y_coverage = overlap(y, top, bottom);
+            for x in x_start..x_end {
+                let coverage = overlap(x, left, right) * y_coverage;
+                self.blend(x, y, color, coverage);
+            }
+        }
+    }
+
+    /// Draws a circle, smoothing its edge by sampling each pixel on a grid.
+    pub fn fill_circle(&mut self, center_x: f32, center_y: f32, radius: f32, color: Color) {
+        let (x_start, x_end) = self.pixel_span(center_x - radius, center_x + radius, self.width);
+        let (y_start, y_end) = self.pixel_span(center_y - radius, center_y + radius, self.height);
+        let radius_squared = radius * radius;
+        let step = 1.0 / CIRCLE_SAMPLES as f32;
+        for y in y_start..y_end {
+            for x in x_start..x_end {
+                let mut inside = 0;
+                for sample_y in 0..CIRCLE_SAMPLES {
+                    let dy = y as f32 + (sample_y as f32 + 0.5) * step - center_y;
+                    for sample_x in 0..CIRCLE_SAMPLES {
+                        let dx = x as f32 + (sample_x as f32 + 0.5) * step - center_x;
+                        if dx * dx + dy * dy <= radius_squared {
+                            inside += 1;
+                        }
+                    }
+                }
+                let coverage = inside as f32 / (CIRCLE_SAMPLES * CIRCLE_SAMPLES) as f32;
+                self.blend(x, y, color, coverage);
+            }
+        }
+    }
+
+    /// Encodes the image as an 8-bit RGBA PNG.
+    pub fn write_png<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        Ok(())
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_png(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Draws `color` over the pixel, with its alpha scaled by `coverage`.
+    fn blend(&mut self, x: u32, y: u32, color: Color, coverage: f32) {
+        let source_alpha = color.a as f32 / 255.0 * coverage;
+        if source_alpha <= 0.0 {
+            return;
+        }
+
+        let offset = self.offset(x, y);
+        let pixel = &mut self.pixels[offset..offset + 4];
+        let target_alpha = pixel[3] as f32 / 255.0;
+        let alpha = source_alpha + target_alpha * (1.0 - source_alpha);
+        let mix = |source: u8, target: u8| {
+            let value = (source as f32 * source_alpha
+                + target as f32 * target_alpha * (1.0 - source_alpha))
+                / alpha;
+            value.round() as u8
+        };
+        pixel[0] = mix(color.r, pixel[0]);
+        pixel[1] = mix(color.g, pixel[1]);
+        pixel[2] = mix(color.b, pixel[2]);
+        pixel[3] = (alpha * 255.0).round() as u8;
+    }
+
+    fn offset(&self, x: u32, y: u32) -> usize {
+        ((y * self.width + x) * 4) as usize
+    }
+
+    /// The pixels touched by the interval from `start` to `end`, clipped to
+    /// the image.
+    fn pixel_span(&self, start: f32, end: f32, limit: u32) -> (u32, u32) {
+        let start = start.floor().max(0.0) as u32;
+        let end = (end.ceil().max(0.0) as u32).min(limit);
+        (start.min(end), end)
+    }
+}
+
+/// How much of the pixel at `index` lies between `start` and `end`.
+fn overlap(index: u32, start: f32, end: f32) -> f32 {
+    let pixel_start = index as f32;
+    let pixel_end = pixel_start + 1.0;
+    (end.min(pixel_end) - start.max(pixel_start)).max(0.0)
+}
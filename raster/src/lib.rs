@@ -0,0 +1,7 @@
+mod board;
+mod image;
+mod svg;
+
+pub use board::{draw_board, draw_board_into, BoardScene, Layout, TileView};
+pub use image::RgbaImage;
+pub use svg::{board_to_svg, save_svg};
@@ -0,0 +1,156 @@
+use crate::board::{BoardScene, Layout, TileView};
+use engine::palette::{self, Color};
+use engine::TileCoordinate;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Describes `scene` as a standalone SVG document. Every tile, dot and the
+/// selection frame is its own shape, so the image stays sharp at any size.
+pub fn board_to_svg(scene: &BoardScene, layout: &Layout) -> String {
+    let (width, height) = layout.board_size(scene.width(), scene.height());
+    let tile_size = layout.tile_size;
+    let mut svg = String::new();
+    // Writing into a String cannot fail.
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    );
+
+    for index in 0..scene.tiles.len() {
+        let tile_coordinate = TileCoordinate {
+            x: scene.index_helper().compute_x_from_index(index),
+            y: scene.index_helper().compute_y_from_index(index),
+        };
+        let (left, top) = layout.tile_origin(&tile_coordinate);
+
+        match scene.tiles[index] {
+            TileView::Covered(state) => {
+                write_rect(
+                    &mut svg,
+                    left,
+                    top,
+                    tile_size,
+                    palette::covered_color(state),
+                );
+                if let Some(Some(probability)) = scene
+                    .probabilities
+                    .as_ref()
+                    .map(|probabilities| probabilities[index])
+                {
+                    let tint = palette::probability_color(probability);
+                    write_rect(&mut svg, left, top, tile_size, tint);
+                }
+            }
+            TileView::Revealed(count) => {
+                write_rect(
+                    &mut svg,
+                    left,
+                    top,
+                    tile_size,
+                    palette::mine_count_color(count),
+                );
+                for (dot_x, dot_y) in palette::dot_positions(count) {
+                    let _ = writeln!(
+                        svg,
+                        r#"  <circle cx="{}" cy="{}" r="{}"{}/>"#,
+                        left + dot_x * tile_size,
+                        top + dot_y * tile_size,
+                        palette::DOT_RADIUS * tile_size,
+                        fill(palette::DOT_COLOR)
+                    );
+                }
+            }
+            TileView::Mine => write_rect(&mut svg, left, top, tile_size, palette::MINE_COLOR),
+        }
+    }
+
+    if let Some(selection) = scene.selection {
+        // A stroke is centered on the outline, so the frame runs half a
+        // margin outside of it to cover the same area as in the raster image.
+        let (x, y) = layout.tile_origin(&selection);
+        let margin = layout.margin;
+        let color = palette::SELECTION_COLOR;
+        let _ = writeln!(
+            svg,
+            r#"  <rect x="{}" y="{}" width="{size}" height="{size}" fill="none" stroke="rgb({},{},{})" stroke-width="{}"/>"#,
+            x - margin / 2.0,
+            y - margin / 2.0,
+            color.r,
+            color.g,
+            color.b,
+            margin,
+            size = tile_size + margin
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Writes `scene` to an SVG file at `path`.
+pub fn save_svg<P: AsRef<Path>>(scene: &BoardScene, layout: &Layout, path: P) -> io::Result<()> {
+    fs::write(path, board_to_svg(scene, layout))
+}
+
+fn write_rect(svg: &mut String, left: f32, top: f32, size: f32, color: Color) {
+    let _ = writeln!(
+        svg,
+        r#"  <rect x="{}" y="{}" width="{size}" height="{size}"{}/>"#,
+        left,
+        top,
+        fill(color),
+        size = size
+    );
+}
+
+/// The fill attributes for `color`, leaving out the opacity when it is opaque.
+fn fill(color: Color) -> String {
+    let mut attributes = format!(r#" fill="rgb({},{},{})""#, color.r, color.g, color.b);
+    if color.a != 255 {
+        let _ = write!(attributes, r#" fill-opacity="{}""#, color.a as f32 / 255.0);
+    }
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_dots_tints_and_the_selection_are_shapes() {
+        let mut scene = BoardScene::new(3, 1);
+        scene.set_tile(&TileCoordinate { x: 0, y: 0 }, TileView::Revealed(1));
+        scene.set_tile(&TileCoordinate { x: 1, y: 0 }, TileView::Mine);
+        scene.probabilities = Some(vec![None, None, Some(1.0)]);
+        scene.selection = Some(TileCoordinate { x: 1, y: 0 });
+        let layout = Layout {
+            tile_size: 12.0,
+            margin: 2.0,
+        };
+
+        assert_eq!(
+            board_to_svg(&scene, &layout),
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="42" height="14" viewBox="0 0 42 14">"#,
+                "\n",
+                r#"  <rect x="1" y="1" width="12" height="12" fill="rgb(173,216,230)"/>"#,
+                "\n",
+                r#"  <circle cx="7" cy="7" r="1" fill="rgb(0,0,0)"/>"#,
+                "\n",
+                r#"  <rect x="15" y="1" width="12" height="12" fill="rgb(255,0,0)"/>"#,
+                "\n",
+                r#"  <rect x="29" y="1" width="12" height="12" fill="rgb(0,0,255)"/>"#,
+                "\n",
+                r#"  <rect x="29" y="1" width="12" height="12" fill="rgb(255,0,0)" fill-opacity="0.627451"/>"#,
+                "\n",
+                r#"  <rect x="14" y="0" width="14" height="14" fill="none" stroke="rgb(255,0,0)" stroke-width="2"/>"#,
+                "\n",
+                "</svg>\n"
+            )
+        );
+    }
+}
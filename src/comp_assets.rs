@@ -1,17 +1,31 @@
-use crate::minesweeper::MineState;
 use bindings::Windows::{
     Foundation::Numerics::Vector2,
     UI::{
-        Colors,
+        Color,
         Composition::{
             CompositionColorBrush, CompositionGeometry, CompositionShape, CompositionSpriteShape,
             Compositor,
         },
     },
 };
+use engine::palette;
+use engine::MineState;
 use std::collections::HashMap;
 use windows::Interface;
 
+/// The number of distinct tints used by the probability overlay.
+const PROBABILITY_STEPS: usize = 20;
+
+/// Converts a color from the palette that every front-end shares.
+pub fn to_ui_color(color: palette::Color) -> Color {
+    Color {
+        A: color.a,
+        R: color.r,
+        G: color.g,
+        B: color.b,
+    }
+}
+
 fn get_dot_shape(
     compositor: &Compositor,
     geometry: &CompositionGeometry,
@@ -29,17 +43,19 @@ pub struct CompAssets {
     mine_state_brushes: HashMap<MineState, CompositionColorBrush>,
     mine_count_background_brushes: HashMap<i32, CompositionColorBrush>,
     mine_count_shapes: HashMap<i32, CompositionShape>,
+    probability_brushes: Vec<CompositionColorBrush>,
 }
 
 impl CompAssets {
     pub fn new(compositor: &Compositor, tile_size: &Vector2) -> windows::Result<Self> {
-        let mine_brush = compositor.CreateColorBrushWithColor(Colors::Red()?)?;
+        let mine_brush = compositor.CreateColorBrushWithColor(to_ui_color(palette::MINE_COLOR))?;
 
         let mut result = Self {
             mine_brush,
             mine_state_brushes: HashMap::new(),
             mine_count_background_brushes: HashMap::new(),
             mine_count_shapes: HashMap::new(),
+            probability_brushes: Vec::new(),
         };
 
         result.generate_assets(compositor, tile_size)?;
@@ -66,346 +82,57 @@ impl CompAssets {
             .clone()
     }
 
+    /// Returns the tint of `palette::probability_color`, rounded to one of a
+    /// few steps so that the brushes can be made up front.
+    pub fn get_probability_brush(&self, probability: f64) -> CompositionColorBrush {
+        let step = (probability.clamp(0.0, 1.0) * PROBABILITY_STEPS as f64).round() as usize;
+        self.probability_brushes[step].clone()
+    }
+
     fn generate_assets(
         &mut self,
         compositor: &Compositor,
         tile_size: &Vector2,
     ) -> windows::Result<()> {
+        let create_brush = |color| compositor.CreateColorBrushWithColor(to_ui_color(color));
+
+        self.probability_brushes.clear();
+        for step in 0..=PROBABILITY_STEPS {
+            let probability = step as f64 / PROBABILITY_STEPS as f64;
+            self.probability_brushes
+                .push(create_brush(palette::probability_color(probability))?);
+        }
+
         self.mine_state_brushes.clear();
-        self.mine_state_brushes.insert(
-            MineState::Empty,
-            compositor.CreateColorBrushWithColor(Colors::Blue()?)?,
-        );
-        self.mine_state_brushes.insert(
-            MineState::Flag,
-            compositor.CreateColorBrushWithColor(Colors::Orange()?)?,
-        );
-        self.mine_state_brushes.insert(
-            MineState::Question,
-            compositor.CreateColorBrushWithColor(Colors::LimeGreen()?)?,
-        );
+        for state in [MineState::Empty, MineState::Flag, MineState::Question] {
+            self.mine_state_brushes
+                .insert(state, create_brush(palette::covered_color(state))?);
+        }
 
         self.mine_count_background_brushes.clear();
-        self.mine_count_background_brushes.insert(
-            1,
-            compositor.CreateColorBrushWithColor(Colors::LightBlue()?)?,
-        );
-        self.mine_count_background_brushes.insert(
-            2,
-            compositor.CreateColorBrushWithColor(Colors::LightGreen()?)?,
-        );
-        self.mine_count_background_brushes.insert(
-            3,
-            compositor.CreateColorBrushWithColor(Colors::LightSalmon()?)?,
-        );
-        self.mine_count_background_brushes.insert(
-            4,
-            compositor.CreateColorBrushWithColor(Colors::LightSteelBlue()?)?,
-        );
-        self.mine_count_background_brushes.insert(
-            5,
-            compositor.CreateColorBrushWithColor(Colors::MediumPurple()?)?,
-        );
-        self.mine_count_background_brushes.insert(
-            6,
-            compositor.CreateColorBrushWithColor(Colors::LightCyan()?)?,
-        );
-        self.mine_count_background_brushes
-            .insert(7, compositor.CreateColorBrushWithColor(Colors::Maroon()?)?);
-        self.mine_count_background_brushes.insert(
-            8,
-            compositor.CreateColorBrushWithColor(Colors::DarkSeaGreen()?)?,
-        );
-        self.mine_count_background_brushes.insert(
-            0,
-            compositor.CreateColorBrushWithColor(Colors::WhiteSmoke()?)?,
-        );
+        for count in 0..=8 {
+            self.mine_count_background_brushes
+                .insert(count, create_brush(palette::mine_count_color(count))?);
+        }
 
         self.mine_count_shapes.clear();
         let circle_geometry = compositor.CreateEllipseGeometry()?;
-        circle_geometry.SetRadius(tile_size / 12.0)?;
+        circle_geometry.SetRadius(tile_size * palette::DOT_RADIUS)?;
         let circle_geometry: CompositionGeometry = circle_geometry.cast()?;
-        let dot_brush = compositor.CreateColorBrushWithColor(Colors::Black()?)?;
-
-        // 1
-        {
-            let container_shape = compositor.CreateContainerShape()?;
-            let shapes = container_shape.Shapes()?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                tile_size / 2.0,
-            )?)?;
-            self.mine_count_shapes.insert(1, container_shape.cast()?);
-        }
-        // 2
-        {
-            let container_shape = compositor.CreateContainerShape()?;
-            let shapes = container_shape.Shapes()?;
-            let third_x = tile_size.X / 3.0;
-            let half_y = tile_size.Y / 2.0;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(third_x, half_y),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(third_x * 2.0, half_y),
-            )?)?;
-            self.mine_count_shapes.insert(2, container_shape.cast()?);
-        }
-        // 3
-        {
-            let container_shape = compositor.CreateContainerShape()?;
-            let shapes = container_shape.Shapes()?;
-            let fourth_x = tile_size.X / 4.0;
-            let fourth_y = tile_size.Y / 4.0;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                tile_size / 2.0,
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x, fourth_y * 3.0),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x * 3.0, fourth_y),
-            )?)?;
-            self.mine_count_shapes.insert(3, container_shape.cast()?);
-        }
-        // 4
-        {
-            let container_shape = compositor.CreateContainerShape()?;
-            let shapes = container_shape.Shapes()?;
-            let third_x = tile_size.X / 3.0;
-            let third_y = tile_size.Y / 3.0;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(third_x, third_y),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(third_x * 2.0, third_y),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(third_x, third_y * 2.0),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(third_x * 2.0, third_y * 2.0),
-            )?)?;
-            self.mine_count_shapes.insert(4, container_shape.cast()?);
-        }
-        // 5
-        {
-            let container_shape = compositor.CreateContainerShape()?;
-            let shapes = container_shape.Shapes()?;
-            let fourth_x = tile_size.X / 4.0;
-            let fourth_y = tile_size.Y / 4.0;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                tile_size / 2.0,
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x, fourth_y * 3.0),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x * 3.0, fourth_y),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x, fourth_y),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x * 3.0, fourth_y * 3.0),
-            )?)?;
-            self.mine_count_shapes.insert(5, container_shape.cast()?);
-        }
-        // 6
-        {
-            let container_shape = compositor.CreateContainerShape()?;
-            let shapes = container_shape.Shapes()?;
-            let fourth_x = tile_size.X / 4.0;
-            let fourth_y = tile_size.Y / 4.0;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x, fourth_y * 2.0),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x, fourth_y * 3.0),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x * 3.0, fourth_y),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x, fourth_y),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x * 3.0, fourth_y * 3.0),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x * 3.0, fourth_y * 2.0),
-            )?)?;
-            self.mine_count_shapes.insert(6, container_shape.cast()?);
-        }
-        // 7
-        {
-            let container_shape = compositor.CreateContainerShape()?;
-            let shapes = container_shape.Shapes()?;
-            let fourth_x = tile_size.X / 4.0;
-            let fourth_y = tile_size.Y / 4.0;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x, fourth_y * 2.0),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x, fourth_y * 3.0),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x * 3.0, fourth_y),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x, fourth_y),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x * 3.0, fourth_y * 3.0),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x * 3.0, fourth_y * 2.0),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                tile_size / 2.0,
-            )?)?;
-            self.mine_count_shapes.insert(7, container_shape.cast()?);
-        }
-        // 8
-        {
+        let dot_brush = create_brush(palette::DOT_COLOR)?;
+        for count in 1..=8 {
             let container_shape = compositor.CreateContainerShape()?;
             let shapes = container_shape.Shapes()?;
-            let fourth_x = tile_size.X / 4.0;
-            let fourth_y = tile_size.Y / 4.0;
-            let half_x = tile_size.X / 2.0;
-            let third_y = tile_size.Y / 3.0;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x, fourth_y * 2.0),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x, fourth_y * 3.0),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x * 3.0, fourth_y),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x, fourth_y),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x * 3.0, fourth_y * 3.0),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(fourth_x * 3.0, fourth_y * 2.0),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(half_x, third_y),
-            )?)?;
-            shapes.Append(get_dot_shape(
-                compositor,
-                &circle_geometry,
-                &dot_brush,
-                Vector2::new(half_x, third_y * 2.0),
-            )?)?;
-            self.mine_count_shapes.insert(8, container_shape.cast()?);
+            for (x, y) in palette::dot_positions(count) {
+                shapes.Append(get_dot_shape(
+                    compositor,
+                    &circle_geometry,
+                    &dot_brush,
+                    Vector2::new(tile_size.X * x, tile_size.Y * y),
+                )?)?;
+            }
+            self.mine_count_shapes
+                .insert(count, container_shape.cast()?);
         }
 
         Ok(())
@@ -1,24 +1,44 @@
-use crate::comp_assets::CompAssets;
-use crate::minesweeper::{IndexHelper, MineState};
-use crate::visual_grid::{TileCoordinate, VisualGrid};
+use crate::comp_assets::{to_ui_color, CompAssets};
+use crate::interop::create_dispatcher_queue_controller_for_current_thread;
+use crate::visual_grid::VisualGrid;
+use crate::window_target::CompositionDesktopWindowTargetSource;
+use crate::window_ui::WindowUI;
 use bindings::Windows::{
     Foundation::{
         Numerics::{Vector2, Vector3},
-        TimeSpan,
+        TimeSpan, TypedEventHandler,
     },
     Graphics::SizeInt32,
+    System::DispatcherQueueController,
+    Win32::System::WinRT::{RoInitialize, RO_INIT_SINGLETHREADED},
     UI::{
-        Colors,
+        Color,
         Composition::{
-            AnimationIterationBehavior, CompositionBatchTypes, CompositionBorderMode, Compositor,
-            ContainerVisual, SpriteVisual,
+            AnimationIterationBehavior, CompositionBatchCompletedEventArgs, CompositionBatchTypes,
+            CompositionBorderMode, CompositionColorBrush, CompositionScopedBatch, Compositor,
+            Desktop::DesktopWindowTarget, SpriteVisual,
         },
     },
 };
+use engine::palette;
+use engine::{ring_delays, BoardRenderer, IndexHelper, MineState, Point, Size, TileCoordinate};
+use std::cell::Cell;
 use std::collections::VecDeque;
+use std::rc::Rc;
 use std::time::Duration;
+use windows::{IInspectable, Interface};
+use winit::window::Window;
+
+/// How long each mine grows and shrinks back when a game is lost.
+const MINE_ANIMATION_DURATION: Duration = Duration::from_millis(600);
+/// The delay between two rings of the win ripple, and how long each tile
+/// flashes.
+const WIN_ANIMATION_DELAY_STEP: Duration = Duration::from_millis(40);
+const WIN_ANIMATION_DURATION: Duration = Duration::from_millis(500);
 
 pub struct CompUI {
+    _controller: DispatcherQueueController,
+    _target: DesktopWindowTarget,
     compositor: Compositor,
     _root: SpriteVisual,
     parent_size: Vector2,
@@ -28,29 +48,47 @@ pub struct CompUI {
     game_board: VisualGrid,
     assets: CompAssets,
 
-    mine_animation_playing: bool,
+    /// The number of the batch whose animations are playing, if any. The
+    /// batch clears it once all of them have completed.
+    playing_batch: Rc<Cell<Option<u32>>>,
+    batch_count: u32,
+    /// The brushes the win ripple gave each tile, with the color to put back
+    /// if the ripple is stopped early.
+    win_brushes: Vec<(CompositionColorBrush, Color)>,
 }
 
 impl CompUI {
-    pub fn new(
-        parent_visual: &ContainerVisual,
-        parent_size: &Vector2,
-        grid_size_in_tiles: &SizeInt32,
-    ) -> windows::Result<Self> {
-        let compositor = parent_visual.Compositor()?;
+    /// Hosts the board in `window` through Windows.UI.Composition. This fails
+    /// for windows that are not Win32 windows.
+    pub fn new(window: &Window, grid_size_in_tiles: &SizeInt32) -> windows::Result<Self> {
+        unsafe { RoInitialize(RO_INIT_SINGLETHREADED)? };
+        let controller = create_dispatcher_queue_controller_for_current_thread()?;
+
+        let compositor = Compositor::new()?;
+        let target = window.create_window_target(&compositor, false)?;
+
+        let parent_visual = compositor.CreateContainerVisual()?;
+        parent_visual.SetRelativeSizeAdjustment(Vector2::new(1.0, 1.0))?;
+        target.SetRoot(&parent_visual)?;
+
+        let window_size = window.inner_size();
+        let parent_size = Vector2::new(window_size.width as f32, window_size.height as f32);
+
         let root = compositor.CreateSpriteVisual()?;
 
         root.SetRelativeSizeAdjustment(Vector2::new(1.0, 1.0))?;
-        root.SetBrush(compositor.CreateColorBrushWithColor(Colors::White()?)?)?;
+        root.SetBrush(
+            compositor.CreateColorBrushWithColor(to_ui_color(palette::BACKGROUND_COLOR))?,
+        )?;
         root.SetBorderMode(CompositionBorderMode::Hard)?;
         parent_visual.Children()?.InsertAtTop(&root)?;
 
-        let tile_size = Vector2::new(25.0, 25.0);
+        let tile_size = Vector2::new(palette::TILE_SIZE, palette::TILE_SIZE);
         let game_board = VisualGrid::new(
             &compositor,
             grid_size_in_tiles,
             &tile_size,
-            &Vector2::new(2.5, 2.5),
+            &Vector2::new(palette::TILE_MARGIN, palette::TILE_MARGIN),
         )?;
         let game_board_margin = Vector2::new(100.0, 100.0);
 
@@ -65,19 +103,148 @@ impl CompUI {
         let assets = CompAssets::new(&compositor, &tile_size)?;
 
         Ok(Self {
+            _controller: controller,
+            _target: target,
             compositor,
             _root: root,
-            parent_size: parent_size.clone(),
+            parent_size,
             game_board_margin,
             index_helper: IndexHelper::new(grid_size_in_tiles.Width, grid_size_in_tiles.Height),
 
             game_board,
             assets,
-            mine_animation_playing: false,
+            playing_batch: Rc::new(Cell::new(None)),
+            batch_count: 0,
+            win_brushes: Vec::new(),
         })
     }
 
-    pub fn hit_test(&self, point: &Vector2) -> windows::Result<Option<TileCoordinate>> {
+    /// Starts a batch that collects the animations started until
+    /// `end_batch`. Input stays locked until they have all completed.
+    fn start_batch(&mut self) -> windows::Result<(CompositionScopedBatch, u32)> {
+        let batch = self
+            .compositor
+            .CreateScopedBatch(CompositionBatchTypes::Animation)?;
+        self.batch_count += 1;
+        self.playing_batch.set(Some(self.batch_count));
+        Ok((batch, self.batch_count))
+    }
+
+    fn end_batch(&self, batch: CompositionScopedBatch, number: u32) -> windows::Result<()> {
+        // A batch that was stopped still completes, possibly after the next
+        // one started, so it only unlocks input for itself.
+        let playing_batch = self.playing_batch.clone();
+        batch.Completed(TypedEventHandler::<
+            IInspectable,
+            CompositionBatchCompletedEventArgs,
+        >::new(move |_, _| {
+            if playing_batch.get() == Some(number) {
+                playing_batch.set(None);
+            }
+            Ok(())
+        }))?;
+        batch.End()
+    }
+
+    fn compute_scale_factor_from_size(&self, window_size: &Vector2) -> windows::Result<f32> {
+        let board_size = self.game_board.size()?;
+        let board_size = board_size + &self.game_board_margin;
+
+        let window_ratio = window_size.X / window_size.Y;
+        let board_ratio = board_size.X / board_size.Y;
+
+        let scale_factor = if window_ratio > board_ratio {
+            window_size.Y / board_size.Y
+        } else {
+            window_size.X / board_size.X
+        };
+
+        Ok(scale_factor)
+    }
+
+    fn compute_scale_factor(&self) -> windows::Result<f32> {
+        self.compute_scale_factor_from_size(&self.parent_size)
+    }
+
+    fn update_board_scale(&mut self, window_size: &Vector2) -> windows::Result<()> {
+        let scale_factor = self.compute_scale_factor_from_size(window_size)?;
+        self.game_board
+            .root()
+            .SetScale(Vector3::new(scale_factor, scale_factor, 1.0))?;
+        Ok(())
+    }
+
+    fn play_mine_animation(&self, index: usize, delay: &TimeSpan) -> windows::Result<()> {
+        let visual = self
+            .game_board
+            .get_tile(
+                self.index_helper.compute_x_from_index(index),
+                self.index_helper.compute_y_from_index(index),
+            )
+            .unwrap();
+        // First, we need to promote the visual to the top.
+        let parent_children = visual.Parent()?.Children()?;
+        parent_children.Remove(visual)?;
+        parent_children.InsertAtTop(visual)?;
+        // Make sure the visual has the mine brush
+        visual.SetBrush(&self.assets.get_mine_brush())?;
+        // Play the animation
+        let animation = self.compositor.CreateVector3KeyFrameAnimation()?;
+        animation.InsertKeyFrame(0.0, Vector3::new(1.0, 1.0, 1.0))?;
+        animation.InsertKeyFrame(0.7, Vector3::new(2.0, 2.0, 1.0))?;
+        animation.InsertKeyFrame(1.0, Vector3::new(1.0, 1.0, 1.0))?;
+        animation.SetDuration(TimeSpan::from(MINE_ANIMATION_DURATION))?;
+        animation.SetDelayTime(delay)?;
+        animation.SetIterationBehavior(AnimationIterationBehavior::Count)?;
+        animation.SetIterationCount(1)?;
+        visual.StartAnimation("Scale", animation)?;
+        Ok(())
+    }
+
+    /// Makes a tile swell slightly and flash gold. The tile gets a brush of
+    /// its own, since the brushes from the assets are shared between tiles.
+    fn play_win_animation_on_tile(
+        &mut self,
+        index: usize,
+        delay: &TimeSpan,
+    ) -> windows::Result<()> {
+        let visual = self
+            .game_board
+            .get_tile(
+                self.index_helper.compute_x_from_index(index),
+                self.index_helper.compute_y_from_index(index),
+            )
+            .unwrap();
+        let color = visual.Brush()?.cast::<CompositionColorBrush>()?.Color()?;
+        let brush = self.compositor.CreateColorBrushWithColor(color)?;
+        visual.SetBrush(&brush)?;
+
+        let color_animation = self.compositor.CreateColorKeyFrameAnimation()?;
+        color_animation.InsertKeyFrame(0.0, color)?;
+        color_animation.InsertKeyFrame(0.5, to_ui_color(palette::WIN_COLOR))?;
+        color_animation.InsertKeyFrame(1.0, color)?;
+        color_animation.SetDuration(TimeSpan::from(WIN_ANIMATION_DURATION))?;
+        color_animation.SetDelayTime(delay)?;
+        brush.StartAnimation("Color", color_animation)?;
+        self.win_brushes.push((brush, color));
+
+        // Tiles are 10% apart, so they only just touch at the peak.
+        let scale_animation = self.compositor.CreateVector3KeyFrameAnimation()?;
+        scale_animation.InsertKeyFrame(0.0, Vector3::new(1.0, 1.0, 1.0))?;
+        scale_animation.InsertKeyFrame(0.5, Vector3::new(1.1, 1.1, 1.0))?;
+        scale_animation.InsertKeyFrame(1.0, Vector3::new(1.0, 1.0, 1.0))?;
+        scale_animation.SetDuration(TimeSpan::from(WIN_ANIMATION_DURATION))?;
+        scale_animation.SetDelayTime(delay)?;
+        visual.StartAnimation("Scale", scale_animation)?;
+        Ok(())
+    }
+}
+
+impl BoardRenderer for CompUI {
+    type Error = windows::Error;
+
+    fn hit_test(&self, point: &Point) -> Result<Option<TileCoordinate>, Self::Error> {
+        let point = Vector2::new(point.x, point.y);
         let window_size = &self.parent_size;
         let scale = self.compute_scale_factor()?;
         let real_board_size = self.game_board.size()? * scale;
@@ -87,37 +254,43 @@ impl CompUI {
         Ok(self.game_board.hit_test(&point))
     }
 
-    pub fn resize(&mut self, new_size: &Vector2) -> windows::Result<()> {
+    fn resize(&mut self, new_size: &Size) -> Result<(), Self::Error> {
+        let new_size = Vector2::new(new_size.width, new_size.height);
         self.parent_size = new_size.clone();
-        self.update_board_scale(new_size)?;
+        self.update_board_scale(&new_size)?;
         Ok(())
     }
 
-    pub fn select_tile(&mut self, tile_coordinate: Option<TileCoordinate>) -> windows::Result<()> {
+    fn select_tile(&mut self, tile_coordinate: Option<TileCoordinate>) -> Result<(), Self::Error> {
         self.game_board.select_tile(tile_coordinate)
     }
 
-    pub fn current_selected_tile(&self) -> Option<TileCoordinate> {
+    fn current_selected_tile(&self) -> Option<TileCoordinate> {
         self.game_board.current_selected_tile()
     }
 
-    pub fn update_tile_with_state(
-        &self,
+    fn update_tile_with_state(
+        &mut self,
         tile_coordinate: &TileCoordinate,
         mine_state: MineState,
-    ) -> windows::Result<()> {
+    ) -> Result<(), Self::Error> {
         let visual = self
             .game_board
             .get_tile(tile_coordinate.x, tile_coordinate.y)
             .unwrap();
 
+        // Undo can cover a revealed tile again, so drop its number.
+        visual.Children()?.RemoveAll()?;
         visual.SetBrush(self.assets.get_color_brush_from_mine_state(mine_state))?;
         Ok(())
     }
 
-    pub fn reset(&mut self, grid_size_in_tiles: &SizeInt32) -> windows::Result<()> {
-        self.game_board.reset(grid_size_in_tiles)?;
-        self.index_helper = IndexHelper::new(grid_size_in_tiles.Width, grid_size_in_tiles.Height);
+    fn reset(&mut self, width: i32, height: i32) -> Result<(), Self::Error> {
+        self.game_board.reset(&SizeInt32 {
+            Width: width,
+            Height: height,
+        })?;
+        self.index_helper = IndexHelper::new(width, height);
 
         for visual in self.game_board.tiles_iter() {
             visual.SetBrush(
@@ -127,12 +300,15 @@ impl CompUI {
         }
 
         self.update_board_scale(&self.parent_size.clone())?;
-        self.mine_animation_playing = false;
+        // Every tile is back on a shared brush, and the new game is not
+        // locked by the animations of the last one.
+        self.playing_batch.set(None);
+        self.win_brushes.clear();
 
         Ok(())
     }
 
-    pub fn update_tile_as_mine(&self, tile_coordinate: &TileCoordinate) -> windows::Result<()> {
+    fn update_tile_as_mine(&mut self, tile_coordinate: &TileCoordinate) -> Result<(), Self::Error> {
         let visual = self
             .game_board
             .get_tile(tile_coordinate.x, tile_coordinate.y)
@@ -142,17 +318,31 @@ impl CompUI {
         Ok(())
     }
 
-    pub fn update_tile_with_mine_count(
-        &self,
+    fn update_tile_as_pressed(
+        &mut self,
+        tile_coordinate: &TileCoordinate,
+    ) -> Result<(), Self::Error> {
+        let visual = self
+            .game_board
+            .get_tile(tile_coordinate.x, tile_coordinate.y)
+            .unwrap();
+        visual.SetBrush(self.assets.get_color_brush_from_mine_count(0))?;
+        Ok(())
+    }
+
+    fn update_tile_with_mine_count(
+        &mut self,
         tile_coordinate: &TileCoordinate,
         num_mines: i32,
-    ) -> windows::Result<()> {
+    ) -> Result<(), Self::Error> {
         let visual = self
             .game_board
             .get_tile(tile_coordinate.x, tile_coordinate.y)
             .unwrap();
         visual.SetBrush(self.assets.get_color_brush_from_mine_count(num_mines))?;
 
+        // Redo can reveal the same tile twice.
+        visual.Children()?.RemoveAll()?;
         if num_mines > 0 {
             let shape = self.assets.get_shape_from_mine_count(num_mines);
             let shape_visual = self.compositor.CreateShapeVisual()?;
@@ -165,98 +355,78 @@ impl CompUI {
         Ok(())
     }
 
-    pub fn play_mine_animations(
+    fn play_mine_animations(
         &mut self,
-        mut mine_indices: VecDeque<usize>,
-        mut mines_per_ring: VecDeque<i32>,
-    ) -> windows::Result<()> {
-        // Create an animation batch so that we can know when the animations complete
-        let batch = self
-            .compositor
-            .CreateScopedBatch(CompositionBatchTypes::Animation)?;
-
+        mine_indices: VecDeque<usize>,
+        mines_per_ring: VecDeque<i32>,
+    ) -> Result<(), Self::Error> {
+        let (batch, number) = self.start_batch()?;
         let animation_delay_step = Duration::from_millis(100);
-        let mut current_delay = Duration::from_millis(0);
-        let mut current_mines_count = 0;
-        while !mine_indices.is_empty() {
-            let mine_index = *mine_indices.front().unwrap();
-            self.play_mine_animation(mine_index, &TimeSpan::from(current_delay))?;
-            current_mines_count += 1;
-
-            let mines_on_current_level = *mines_per_ring.front().unwrap();
-            if current_mines_count == mines_on_current_level {
-                current_mines_count = 0;
-                mines_per_ring.pop_front().unwrap();
-                current_delay += animation_delay_step;
-            }
-            mine_indices.pop_front().unwrap();
+        let delays = ring_delays(mine_indices, mines_per_ring, animation_delay_step);
+        for (mine_index, delay) in &delays {
+            self.play_mine_animation(*mine_index, &TimeSpan::from(*delay))?;
         }
-
-        // Subscribe to the completion event and complete the batch
-        // TODO: events
-        batch.End()?;
-
-        self.mine_animation_playing = true;
-
-        Ok(())
+        self.end_batch(batch, number)
     }
 
-    pub fn is_animation_playing(&self) -> bool {
-        self.mine_animation_playing
+    fn play_win_animation(
+        &mut self,
+        tile_indices: VecDeque<usize>,
+        tiles_per_ring: VecDeque<i32>,
+    ) -> Result<(), Self::Error> {
+        let (batch, number) = self.start_batch()?;
+        let delays = ring_delays(tile_indices, tiles_per_ring, WIN_ANIMATION_DELAY_STEP);
+        for (tile_index, delay) in &delays {
+            self.play_win_animation_on_tile(*tile_index, &TimeSpan::from(*delay))?;
+        }
+        self.end_batch(batch, number)
     }
 
-    fn compute_scale_factor_from_size(&self, window_size: &Vector2) -> windows::Result<f32> {
-        let board_size = self.game_board.size()?;
-        let board_size = board_size + &self.game_board_margin;
-
-        let window_ratio = window_size.X / window_size.Y;
-        let board_ratio = board_size.X / board_size.Y;
+    fn is_animation_playing(&self) -> bool {
+        self.playing_batch.get().is_some()
+    }
 
-        let scale_factor = if window_ratio > board_ratio {
-            window_size.Y / board_size.Y
-        } else {
-            window_size.X / board_size.X
-        };
+    fn stop_animations(&mut self) -> Result<(), Self::Error> {
+        for visual in self.game_board.tiles_iter() {
+            visual.StopAnimation("Scale")?;
+            visual.SetScale(Vector3::new(1.0, 1.0, 1.0))?;
+        }
+        for (brush, color) in self.win_brushes.drain(..) {
+            brush.StopAnimation("Color")?;
+            brush.SetColor(color)?;
+        }
+        // The animation moved the mines above the overlay.
+        self.game_board.raise_overlay()?;
+        self.playing_batch.set(None);
+        Ok(())
+    }
 
-        Ok(scale_factor)
+    fn show_probabilities(&mut self, probabilities: &[Option<f64>]) -> Result<(), Self::Error> {
+        for (index, probability) in probabilities.iter().enumerate() {
+            let visual = self.game_board.get_overlay_tile(index).unwrap();
+            match probability {
+                Some(probability) => {
+                    visual.SetBrush(self.assets.get_probability_brush(*probability))?;
+                    visual.SetIsVisible(true)?;
+                }
+                None => visual.SetIsVisible(false)?,
+            }
+        }
+        self.game_board.set_overlay_visible(true)
     }
 
-    fn compute_scale_factor(&self) -> windows::Result<f32> {
-        self.compute_scale_factor_from_size(&self.parent_size)
+    fn hide_probabilities(&mut self) -> Result<(), Self::Error> {
+        self.game_board.set_overlay_visible(false)
     }
+}
 
-    fn update_board_scale(&mut self, window_size: &Vector2) -> windows::Result<()> {
-        let scale_factor = self.compute_scale_factor_from_size(window_size)?;
-        self.game_board
-            .root()
-            .SetScale(Vector3::new(scale_factor, scale_factor, 1.0))?;
+impl WindowUI for CompUI {
+    fn redraw(&mut self) -> Result<(), Self::Error> {
+        // The compositor draws the visuals on its own.
         Ok(())
     }
 
-    fn play_mine_animation(&self, index: usize, delay: &TimeSpan) -> windows::Result<()> {
-        let visual = self
-            .game_board
-            .get_tile(
-                self.index_helper.compute_x_from_index(index),
-                self.index_helper.compute_y_from_index(index),
-            )
-            .unwrap();
-        // First, we need to promote the visual to the top.
-        let parent_children = visual.Parent()?.Children()?;
-        parent_children.Remove(visual)?;
-        parent_children.InsertAtTop(visual)?;
-        // Make sure the visual has the mine brush
-        visual.SetBrush(&self.assets.get_mine_brush())?;
-        // Play the animation
-        let animation = self.compositor.CreateVector3KeyFrameAnimation()?;
-        animation.InsertKeyFrame(0.0, Vector3::new(1.0, 1.0, 1.0))?;
-        animation.InsertKeyFrame(0.7, Vector3::new(2.0, 2.0, 1.0))?;
-        animation.InsertKeyFrame(1.0, Vector3::new(1.0, 1.0, 1.0))?;
-        animation.SetDuration(TimeSpan::from(Duration::from_millis(600)))?;
-        animation.SetDelayTime(delay)?;
-        animation.SetIterationBehavior(AnimationIterationBehavior::Count)?;
-        animation.SetIterationCount(1)?;
-        visual.StartAnimation("Scale", animation)?;
-        Ok(())
+    fn needs_frames(&self) -> bool {
+        false
     }
 }
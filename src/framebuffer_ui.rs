@@ -0,0 +1,315 @@
+use crate::window_ui::WindowUI;
+use engine::palette::{self, Color};
+use engine::{ring_delays, BoardRenderer, MineState, Point, Size, TileCoordinate};
+use raster::{draw_board_into, BoardScene, Layout, RgbaImage, TileView};
+use softbuffer::{Context, SoftBufferError, Surface};
+use std::collections::VecDeque;
+use std::num::NonZeroU32;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use winit::window::Window;
+
+/// The space kept free around the board when it is scaled to fit the
+/// window, as in the composition front-end.
+const BOARD_MARGIN: f32 = 100.0;
+
+/// The delay between two rings of the mine animation.
+const ANIMATION_DELAY_STEP: Duration = Duration::from_millis(100);
+/// How long each mine grows and shrinks back.
+const ANIMATION_DURATION: Duration = Duration::from_millis(600);
+/// How far into the animation a mine is at its largest, and how large that
+/// is.
+const ANIMATION_PEAK: f32 = 0.7;
+const ANIMATION_PEAK_SCALE: f32 = 2.0;
+
+/// The delay between two rings of the win ripple, and how long each tile
+/// glows.
+const RIPPLE_DELAY_STEP: Duration = Duration::from_millis(40);
+const RIPPLE_DURATION: Duration = Duration::from_millis(500);
+/// How large a tile is at the height of its glow.
+const RIPPLE_PEAK_SCALE: f32 = 1.1;
+
+/// Draws the board on the CPU and copies it into the window. Works with any
+/// window that winit can open.
+pub struct FramebufferUI {
+    _context: Context<Rc<Window>>,
+    surface: Surface<Rc<Window>, Rc<Window>>,
+    image: RgbaImage,
+    parent_size: Size,
+    scene: BoardScene,
+
+    /// Each animated mine with the delay after which it starts to grow.
+    animated_mines: Vec<(usize, Duration)>,
+    /// Each tile of the win ripple with the delay after which it glows.
+    rippled_tiles: Vec<(usize, Duration)>,
+    animation_started: Option<Instant>,
+}
+
+impl FramebufferUI {
+    pub fn new(window: Rc<Window>) -> Result<Self, SoftBufferError> {
+        let context = Context::new(window.clone())?;
+        let surface = Surface::new(&context, window.clone())?;
+        let window_size = window.inner_size();
+
+        Ok(Self {
+            _context: context,
+            surface,
+            image: RgbaImage::new(0, 0),
+            parent_size: Size::new(window_size.width as f32, window_size.height as f32),
+            scene: BoardScene::new(0, 0),
+
+            animated_mines: Vec::new(),
+            rippled_tiles: Vec::new(),
+            animation_started: None,
+        })
+    }
+
+    /// The layout that fits the board into the window, and where the top left
+    /// corner of the board goes.
+    fn board_placement(&self) -> (Layout, (f32, f32)) {
+        let (board_width, board_height) =
+            Layout::default().board_size(self.scene.width(), self.scene.height());
+        let scale = (self.parent_size.width / (board_width + BOARD_MARGIN))
+            .min(self.parent_size.height / (board_height + BOARD_MARGIN));
+        let layout = Layout::scaled(scale);
+        let (board_width, board_height) =
+            layout.board_size(self.scene.width(), self.scene.height());
+        let offset = (
+            (self.parent_size.width - board_width) / 2.0,
+            (self.parent_size.height - board_height) / 2.0,
+        );
+        (layout, offset)
+    }
+
+    /// Draws the whole window into `image`.
+    fn draw(&self, image: &mut RgbaImage) {
+        image.clear(palette::BACKGROUND_COLOR);
+        let (layout, offset) = self.board_placement();
+        draw_board_into(image, &self.scene, &layout, offset);
+
+        // Growing mines are drawn on top of their neighbors.
+        let elapsed = self
+            .animation_started
+            .map(|started| started.elapsed())
+            .unwrap_or_default();
+        for (index, delay) in &self.animated_mines {
+            let scale = match elapsed.checked_sub(*delay) {
+                Some(time) if time < ANIMATION_DURATION => mine_scale(time),
+                _ => continue,
+            };
+            self.fill_tile(image, &layout, offset, *index, scale, palette::MINE_COLOR);
+        }
+        for (index, delay) in &self.rippled_tiles {
+            let strength = match elapsed.checked_sub(*delay) {
+                Some(time) if time < RIPPLE_DURATION => ripple_strength(time),
+                _ => continue,
+            };
+            let scale = 1.0 + (RIPPLE_PEAK_SCALE - 1.0) * strength;
+            let color = palette::WIN_COLOR;
+            let color = Color::rgba(color.r, color.g, color.b, (strength * 255.0) as u8);
+            self.fill_tile(image, &layout, offset, *index, scale, color);
+        }
+    }
+
+    /// Covers the tile at `index` with `color`, scaled around its center.
+    fn fill_tile(
+        &self,
+        image: &mut RgbaImage,
+        layout: &Layout,
+        offset: (f32, f32),
+        index: usize,
+        scale: f32,
+        color: Color,
+    ) {
+        let tile_coordinate = TileCoordinate {
+            x: self.scene.index_helper().compute_x_from_index(index),
+            y: self.scene.index_helper().compute_y_from_index(index),
+        };
+        let (x, y) = layout.tile_origin(&tile_coordinate);
+        let half_size = layout.tile_size / 2.0;
+        let (center_x, center_y) = (offset.0 + x + half_size, offset.1 + y + half_size);
+        image.fill_rect(
+            center_x - half_size * scale,
+            center_y - half_size * scale,
+            center_x + half_size * scale,
+            center_y + half_size * scale,
+            color,
+        );
+    }
+}
+
+/// How strongly a tile glows `time` into the win ripple, rising to full
+/// halfway through and fading out again.
+fn ripple_strength(time: Duration) -> f32 {
+    let progress = time.as_secs_f32() / RIPPLE_DURATION.as_secs_f32();
+    1.0 - (2.0 * progress - 1.0).abs()
+}
+
+/// The scale of a mine `time` into its animation. It grows to its peak and
+/// shrinks back, like the key frames of the composition animation.
+fn mine_scale(time: Duration) -> f32 {
+    let progress = time.as_secs_f32() / ANIMATION_DURATION.as_secs_f32();
+    let growth = ANIMATION_PEAK_SCALE - 1.0;
+    if progress < ANIMATION_PEAK {
+        1.0 + growth * progress / ANIMATION_PEAK
+    } else {
+        ANIMATION_PEAK_SCALE - growth * (progress - ANIMATION_PEAK) / (1.0 - ANIMATION_PEAK)
+    }
+}
+
+impl BoardRenderer for FramebufferUI {
+    type Error = SoftBufferError;
+
+    fn reset(&mut self, width: i32, height: i32) -> Result<(), Self::Error> {
+        self.scene = BoardScene::new(width, height);
+        self.stop_animations()
+    }
+
+    fn resize(&mut self, new_size: &Size) -> Result<(), Self::Error> {
+        self.parent_size = *new_size;
+        Ok(())
+    }
+
+    fn hit_test(&self, point: &Point) -> Result<Option<TileCoordinate>, Self::Error> {
+        let (layout, offset) = self.board_placement();
+        let step = layout.tile_size + layout.margin;
+        let x = ((point.x - offset.0) / step).floor() as i32;
+        let y = ((point.y - offset.1) / step).floor() as i32;
+        if self.scene.index_helper().is_in_bounds(x, y) {
+            Ok(Some(TileCoordinate { x, y }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn select_tile(&mut self, tile_coordinate: Option<TileCoordinate>) -> Result<(), Self::Error> {
+        self.scene.selection = tile_coordinate;
+        Ok(())
+    }
+
+    fn current_selected_tile(&self) -> Option<TileCoordinate> {
+        self.scene.selection
+    }
+
+    fn update_tile_with_state(
+        &mut self,
+        tile_coordinate: &TileCoordinate,
+        mine_state: MineState,
+    ) -> Result<(), Self::Error> {
+        self.scene
+            .set_tile(tile_coordinate, TileView::Covered(mine_state));
+        Ok(())
+    }
+
+    fn update_tile_as_mine(&mut self, tile_coordinate: &TileCoordinate) -> Result<(), Self::Error> {
+        self.scene.set_tile(tile_coordinate, TileView::Mine);
+        Ok(())
+    }
+
+    fn update_tile_as_pressed(
+        &mut self,
+        tile_coordinate: &TileCoordinate,
+    ) -> Result<(), Self::Error> {
+        self.scene.set_tile(tile_coordinate, TileView::Revealed(0));
+        Ok(())
+    }
+
+    fn update_tile_with_mine_count(
+        &mut self,
+        tile_coordinate: &TileCoordinate,
+        num_mines: i32,
+    ) -> Result<(), Self::Error> {
+        self.scene
+            .set_tile(tile_coordinate, TileView::Revealed(num_mines));
+        Ok(())
+    }
+
+    fn play_mine_animations(
+        &mut self,
+        mine_indices: VecDeque<usize>,
+        mines_per_ring: VecDeque<i32>,
+    ) -> Result<(), Self::Error> {
+        // Every mine is shown right away and then grows once its ring comes.
+        for mine_index in &mine_indices {
+            self.scene.tiles[*mine_index] = TileView::Mine;
+        }
+        self.animated_mines = ring_delays(mine_indices, mines_per_ring, ANIMATION_DELAY_STEP);
+        self.animation_started = Some(Instant::now());
+
+        Ok(())
+    }
+
+    fn play_win_animation(
+        &mut self,
+        tile_indices: VecDeque<usize>,
+        tiles_per_ring: VecDeque<i32>,
+    ) -> Result<(), Self::Error> {
+        self.rippled_tiles = ring_delays(tile_indices, tiles_per_ring, RIPPLE_DELAY_STEP);
+        self.animation_started = Some(Instant::now());
+        Ok(())
+    }
+
+    fn is_animation_playing(&self) -> bool {
+        let end = |tiles: &[(usize, Duration)], duration: Duration| {
+            tiles
+                .last()
+                .map(|(_, delay)| *delay + duration)
+                .unwrap_or_default()
+        };
+        let end = end(&self.animated_mines, ANIMATION_DURATION)
+            .max(end(&self.rippled_tiles, RIPPLE_DURATION));
+        self.animation_started
+            .is_some_and(|started| started.elapsed() < end)
+    }
+
+    fn stop_animations(&mut self) -> Result<(), Self::Error> {
+        self.animated_mines.clear();
+        self.rippled_tiles.clear();
+        self.animation_started = None;
+        Ok(())
+    }
+
+    fn show_probabilities(&mut self, probabilities: &[Option<f64>]) -> Result<(), Self::Error> {
+        self.scene.probabilities = Some(probabilities.to_vec());
+        Ok(())
+    }
+
+    fn hide_probabilities(&mut self) -> Result<(), Self::Error> {
+        self.scene.probabilities = None;
+        Ok(())
+    }
+}
+
+impl WindowUI for FramebufferUI {
+    fn redraw(&mut self) -> Result<(), Self::Error> {
+        // A replay keeps the size it was recorded with, so the board can be
+        // laid out for another size than the window has.
+        let window_size = self.surface.window().inner_size();
+        let (width, height) = (window_size.width, window_size.height);
+        let (buffer_width, buffer_height) = match (NonZeroU32::new(width), NonZeroU32::new(height))
+        {
+            (Some(width), Some(height)) => (width, height),
+            // A minimized window has nothing to draw into.
+            _ => return Ok(()),
+        };
+        self.surface.resize(buffer_width, buffer_height)?;
+
+        let mut image = std::mem::replace(&mut self.image, RgbaImage::new(0, 0));
+        if image.width() != width || image.height() != height {
+            image = RgbaImage::new(width, height);
+        }
+        self.draw(&mut image);
+
+        let mut buffer = self.surface.buffer_mut()?;
+        for (pixel, rgba) in buffer.iter_mut().zip(image.pixels().chunks_exact(4)) {
+            *pixel = (rgba[0] as u32) << 16 | (rgba[1] as u32) << 8 | rgba[2] as u32;
+        }
+        buffer.present()?;
+        self.image = image;
+        Ok(())
+    }
+
+    fn needs_frames(&self) -> bool {
+        self.is_animation_playing()
+    }
+}
@@ -3,6 +3,7 @@ use bindings::Windows::Win32::System::WinRT::{
     CreateDispatcherQueueController, DispatcherQueueOptions, DISPATCHERQUEUE_THREAD_APARTMENTTYPE,
     DISPATCHERQUEUE_THREAD_TYPE, DQTAT_COM_NONE, DQTYPE_THREAD_CURRENT,
 };
+use bindings::Windows::Win32::UI::PointerInput::{GetPointerPenInfo, POINTER_PEN_INFO};
 
 pub fn create_dispatcher_queue_controller(
     thread_type: DISPATCHERQUEUE_THREAD_TYPE,
@@ -20,3 +21,15 @@ pub fn create_dispatcher_queue_controller_for_current_thread(
 ) -> windows::Result<DispatcherQueueController> {
     create_dispatcher_queue_controller(DQTYPE_THREAD_CURRENT, DQTAT_COM_NONE)
 }
+
+/// Set in `POINTER_PEN_INFO::penFlags` while the eraser end of a pen is used.
+const PEN_FLAG_ERASER: u32 = 0x4;
+
+/// Whether the pointer with `pointer_id` is the eraser end of a pen. Only
+/// meaningful while a message about that pointer is being handled, which is
+/// when winit reports it as a touch.
+pub fn is_pen_eraser(pointer_id: u32) -> bool {
+    let mut pen_info = POINTER_PEN_INFO::default();
+    unsafe { GetPointerPenInfo(pointer_id, &mut pen_info) }.as_bool()
+        && pen_info.penFlags & PEN_FLAG_ERASER != 0
+}
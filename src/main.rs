@@ -1,87 +1,510 @@
 #![windows_subsystem = "windows"]
 
+#[cfg(windows)]
 mod comp_assets;
+#[cfg(windows)]
 mod comp_ui;
+mod framebuffer_ui;
+#[cfg(windows)]
 mod interop;
-mod minesweeper;
+#[cfg(windows)]
 mod numerics;
+#[cfg(windows)]
 mod visual_grid;
+#[cfg(windows)]
 mod window_target;
+mod window_ui;
 
-use interop::create_dispatcher_queue_controller_for_current_thread;
-use minesweeper::Minesweeper;
-use window_target::CompositionDesktopWindowTargetSource;
-use winit::{
-    event::{ElementState, Event, MouseButton, WindowEvent},
-    event_loop::{ControlFlow, EventLoop},
-    window::WindowBuilder,
+use engine::args::{config_from_args, parse_arg};
+use engine::{
+    Bindings, BoardRenderer, Command, Gesture, Input, MineState, Minesweeper, Point, PointerButton,
+    Replay, ReplayPlayer, SavedGame, Size,
 };
-
-use bindings::Windows::{
-    Foundation::Numerics::Vector2,
-    Win32::System::WinRT::{RoInitialize, RO_INIT_SINGLETHREADED},
-    UI::Composition::Compositor,
+use framebuffer_ui::FramebufferUI;
+use std::error::Error;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use window_ui::WindowUI;
+use winit::{
+    event::{
+        ElementState, Event, KeyEvent, MouseButton, StartCause, Touch, TouchPhase, WindowEvent,
+    },
+    event_loop::{ControlFlow, EventLoop, EventLoopWindowTarget},
+    keyboard::{Key, ModifiersState, NamedKey},
+    window::{Window, WindowBuilder},
 };
 
-fn run() -> windows::Result<()> {
-    unsafe { RoInitialize(RO_INIT_SINGLETHREADED)? };
-    let _controller = create_dispatcher_queue_controller_for_current_thread()?;
+/// How often a replay is advanced and animations are drawn while they play.
+const FRAME_TIME: Duration = Duration::from_millis(16);
+/// How far the arrow keys seek in a replay.
+const SEEK_STEP: Duration = Duration::from_secs(5);
+/// Where an unfinished game is kept between runs, unless `--save-file` says
+/// otherwise.
+const DEFAULT_SAVE_FILE: &str = "minesweeper.save";
+/// Where the input bindings are read from, unless `--bindings` says
+/// otherwise.
+const DEFAULT_BINDINGS_FILE: &str = "minesweeper.bindings";
+/// How long a finger has to stay down to make a long press.
+const LONG_PRESS_TIME: Duration = Duration::from_millis(500);
 
-    let event_loop = EventLoop::new();
-    let window = WindowBuilder::new().build(&event_loop).unwrap();
-    window.set_title("Minesweeper");
+fn run() -> Result<(), Box<dyn Error>> {
+    let event_loop = EventLoop::new()?;
+    let window = Rc::new(WindowBuilder::new().build(&event_loop)?);
 
-    let compositor = Compositor::new()?;
-    let target = window.create_window_target(&compositor, false)?;
+    // Composition is only there on Windows, and even there `--software`
+    // draws the board on the CPU like everywhere else.
+    #[cfg(windows)]
+    if !engine::args::has_flag("--software") {
+        let grid_size = bindings::Windows::Graphics::SizeInt32 {
+            Width: 16,
+            Height: 16,
+        };
+        match comp_ui::CompUI::new(&window, &grid_size) {
+            Ok(ui) => return run_game(event_loop, window, ui),
+            Err(error) => eprintln!("Falling back to software rendering: {}", error),
+        }
+    }
 
-    let root = compositor.CreateContainerVisual()?;
-    root.SetRelativeSizeAdjustment(Vector2::new(1.0, 1.0))?;
-    target.SetRoot(&root)?;
+    let ui = FramebufferUI::new(window.clone())?;
+    run_game(event_loop, window, ui)
+}
 
+fn run_game<UI>(event_loop: EventLoop<()>, window: Rc<Window>, ui: UI) -> Result<(), Box<dyn Error>>
+where
+    UI: WindowUI,
+    UI::Error: Error + 'static,
+{
     let window_size = window.inner_size();
-    let window_size = Vector2::new(window_size.width as f32, window_size.height as f32);
-    let mut game = Minesweeper::new(&root, &window_size)?;
+    let window_size = Size::new(window_size.width as f32, window_size.height as f32);
+    let seed = parse_arg("--seed")?;
+    let mut game = Minesweeper::new(ui, &window_size, config_from_args()?, seed)?;
+
+    // An unfinished game from the last run is picked up again, unless the
+    // command line asks for a specific board.
+    let save_path = parse_arg("--save-file")?.unwrap_or_else(|| DEFAULT_SAVE_FILE.to_string());
+    let replay_path: Option<String> = parse_arg("--replay")?;
+    if seed.is_none() && replay_path.is_none() && Path::new(&save_path).exists() {
+        match SavedGame::load_from_file(&save_path) {
+            Ok(saved) => game.resume(saved)?,
+            Err(error) => eprintln!("Ignoring the saved game: {}", error),
+        }
+    }
+
+    // Like the save, the bindings are looked for in the working directory.
+    let bindings = match parse_arg::<String>("--bindings")? {
+        Some(path) => load_bindings(&path),
+        None if Path::new(DEFAULT_BINDINGS_FILE).exists() => load_bindings(DEFAULT_BINDINGS_FILE),
+        None => Bindings::default(),
+    };
+
+    // While a replay plays, live pointer input is ignored.
+    let record_path: Option<String> = parse_arg("--record")?;
+    let mut player = match replay_path {
+        Some(path) => match Replay::load_from_file(&path) {
+            Ok(replay) => {
+                let mut replay_player = ReplayPlayer::new(replay);
+                replay_player.start(&mut game)?;
+                Some(replay_player)
+            }
+            Err(error) => {
+                eprintln!("Ignoring --replay: {}", error);
+                None
+            }
+        },
+        None => None,
+    };
+    let mut last_tick = Instant::now();
+
+    let mut parent_size = window_size;
+    let mut modifiers = ModifiersState::empty();
+    let mut buttons = HeldButtons::default();
+    let mut held_touch = None;
+    let mut displayed_seed = game.seed();
+    window.set_title(&window_title(displayed_seed));
+
+    event_loop.run(move |event, event_loop| {
+        if let Some(player) = &mut player {
+            let now = Instant::now();
+            player.advance(&mut game, now - last_tick).unwrap();
+            last_tick = now;
+        }
+
+        // Anything the player does may change the board, so it is drawn
+        // again afterwards.
+        let is_input = matches!(
+            &event,
+            Event::WindowEvent { event, .. } if !matches!(event, WindowEvent::RedrawRequested)
+        );
 
-    event_loop.run(move |event, _, control_flow| {
-        *control_flow = ControlFlow::Wait;
         match event {
+            Event::NewEvents(StartCause::ResumeTimeReached { .. }) => window.request_redraw(),
             Event::WindowEvent {
                 event: WindowEvent::CloseRequested,
                 window_id,
-            } if window_id == window.id() => *control_flow = ControlFlow::Exit,
+            } if window_id == window.id() => event_loop.exit(),
+            // Closing the window and the quit command both end up here.
+            Event::LoopExiting => {
+                if let Some(path) = &record_path {
+                    match game.replay() {
+                        Some(replay) => {
+                            if let Err(error) = replay.save_to_file(path) {
+                                eprintln!("Failed to save the replay: {}", error);
+                            }
+                        }
+                        None => eprintln!("A resumed game cannot be recorded"),
+                    }
+                }
+                // A replay is not the player's own game, so it leaves the
+                // saved game alone.
+                if player.is_none() {
+                    if let Err(error) = save_game(&game, &save_path) {
+                        eprintln!("Failed to save the game: {}", error);
+                    }
+                }
+            }
+            Event::WindowEvent {
+                event: WindowEvent::RedrawRequested,
+                ..
+            } => game.ui_mut().redraw().unwrap(),
             Event::WindowEvent {
                 event: WindowEvent::Resized(size),
                 ..
             } => {
-                let size = Vector2::new(size.width as f32, size.height as f32);
-                game.on_parent_size_changed(&size).unwrap();
+                // A replay keeps the window size it was recorded with, so that
+                // its pointer positions hit the same tiles.
+                parent_size = Size::new(size.width as f32, size.height as f32);
+                if player.is_none() {
+                    game.on_parent_size_changed(&parent_size).unwrap();
+                }
             }
+            Event::WindowEvent {
+                event: WindowEvent::ModifiersChanged(new_modifiers),
+                ..
+            } => modifiers = new_modifiers.state(),
             Event::WindowEvent {
                 event: WindowEvent::CursorMoved { position, .. },
                 ..
-            } => {
-                let point = Vector2::new(position.x as f32, position.y as f32);
+            } if player.is_none() => {
+                let point = Point::new(position.x as f32, position.y as f32);
                 game.on_pointer_moved(&point).unwrap();
             }
             Event::WindowEvent {
                 event: WindowEvent::MouseInput { state, button, .. },
                 ..
-            } => {
-                if state == ElementState::Pressed {
-                    game.on_pointer_pressed(button == MouseButton::Right, false)
-                        .unwrap();
+            } if player.is_none() => on_mouse_button(
+                &mut game,
+                &window,
+                event_loop,
+                &bindings,
+                &mut buttons,
+                state,
+                button,
+            )
+            .unwrap(),
+            Event::WindowEvent {
+                event: WindowEvent::Touch(touch),
+                ..
+            } if player.is_none() => on_touch(
+                &mut game,
+                &window,
+                event_loop,
+                &bindings,
+                &mut held_touch,
+                touch,
+            )
+            .unwrap(),
+            Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                logical_key,
+                                state: ElementState::Pressed,
+                                ..
+                            },
+                        ..
+                    },
+                ..
+            } => match &mut player {
+                Some(replay_player) => {
+                    if on_playback_key(replay_player, &mut game, &logical_key).unwrap() {
+                        player = None;
+                        game.on_parent_size_changed(&parent_size).unwrap();
+                    }
                 }
-            }
+                None => on_key(
+                    &mut game,
+                    &window,
+                    event_loop,
+                    &bindings,
+                    &logical_key,
+                    modifiers,
+                )
+                .unwrap(),
+            },
             _ => (),
         }
-    });
+
+        if is_input {
+            window.request_redraw();
+        }
+
+        // Keep ticking while a replay is loaded, even when it is paused or
+        // finished, since the playback controls can resume it at any time.
+        if player.is_some() || game.ui().needs_frames() {
+            event_loop.set_control_flow(ControlFlow::WaitUntil(Instant::now() + FRAME_TIME));
+        } else {
+            event_loop.set_control_flow(ControlFlow::Wait);
+        }
+
+        // Keep the seed in the title bar so that any board can be shared.
+        if game.seed() != displayed_seed {
+            displayed_seed = game.seed();
+            window.set_title(&window_title(displayed_seed));
+        }
+    })?;
+    Ok(())
 }
 
-fn main() {
-    let result = run();
+/// The buttons that are held down, by the command they are bound to, and
+/// whether they make a chord.
+#[derive(Default)]
+struct HeldButtons {
+    reveal: bool,
+    mark: bool,
+    is_chording: bool,
+}
+
+/// Chords with a button bound to chording, with the reveal and mark buttons
+/// at once and with the reveal button on a revealed tile. The chord happens
+/// when the first of its buttons is let go. Otherwise the reveal button
+/// reveals when it comes up, so that it can still become part of a chord,
+/// and every other button acts when it goes down.
+fn on_mouse_button<UI: WindowUI>(
+    game: &mut Minesweeper<UI>,
+    window: &Window,
+    event_loop: &EventLoopWindowTarget<()>,
+    bindings: &Bindings,
+    buttons: &mut HeldButtons,
+    state: ElementState,
+    button: MouseButton,
+) -> Result<(), UI::Error> {
+    let button = match button {
+        MouseButton::Left => PointerButton::Left,
+        MouseButton::Right => PointerButton::Right,
+        MouseButton::Middle => PointerButton::Middle,
+        _ => return Ok(()),
+    };
+    let command = match bindings.command(&Input::Mouse(button)) {
+        Some(command) => command,
+        None => return Ok(()),
+    };
+    let is_pressed = state == ElementState::Pressed;
+    let was_reveal_held = buttons.reveal;
+    match command {
+        Command::Reveal => buttons.reveal = is_pressed,
+        Command::Mark => buttons.mark = is_pressed,
+        _ => (),
+    }
 
-    // We do this for nicer HRESULT printing when errors occur.
-    if let Err(error) = result {
-        error.code().unwrap();
+    if is_pressed {
+        let is_on_revealed_tile = game
+            .current_tile()
+            .is_some_and(|tile| game.game().mine_state(tile.x, tile.y) == MineState::Revealed);
+        let is_chord = !game.game().is_game_over()
+            && (command == Command::Chord
+                || (buttons.reveal && buttons.mark)
+                || (command == Command::Reveal && is_on_revealed_tile));
+        if is_chord {
+            buttons.is_chording = true;
+            return game.on_chord_pressed();
+        }
+        if command != Command::Reveal {
+            return run_command(game, window, event_loop, command);
+        }
+    } else if buttons.is_chording {
+        if matches!(command, Command::Reveal | Command::Mark | Command::Chord) {
+            // The other button coming up later must not reveal anything.
+            *buttons = HeldButtons::default();
+            return game.on_chord_released();
+        }
+    } else if command == Command::Reveal && was_reveal_held {
+        return game.execute(Command::Reveal);
+    }
+    Ok(())
+}
+
+/// The finger or pen that is on the screen, since when, and whether it is
+/// the eraser end of a pen. Other fingers are ignored until it is lifted.
+struct HeldTouch {
+    id: u64,
+    started: Instant,
+    is_eraser: bool,
+}
+
+/// Follows one finger like the pointer, and runs the command of a tap or a
+/// long press when it is lifted. A pen's eraser runs its own command instead,
+/// however long it was held.
+fn on_touch<UI: WindowUI>(
+    game: &mut Minesweeper<UI>,
+    window: &Window,
+    event_loop: &EventLoopWindowTarget<()>,
+    bindings: &Bindings,
+    held_touch: &mut Option<HeldTouch>,
+    touch: Touch,
+) -> Result<(), UI::Error> {
+    let is_held = held_touch.as_ref().is_some_and(|held| held.id == touch.id);
+    let point = Point::new(touch.location.x as f32, touch.location.y as f32);
+    match touch.phase {
+        TouchPhase::Started if held_touch.is_none() => {
+            *held_touch = Some(HeldTouch {
+                id: touch.id,
+                started: Instant::now(),
+                is_eraser: is_pen_eraser(&touch),
+            });
+            game.on_pointer_moved(&point)
+        }
+        TouchPhase::Moved if is_held => game.on_pointer_moved(&point),
+        TouchPhase::Ended if is_held => {
+            let held = held_touch.take().unwrap();
+            let input = if held.is_eraser {
+                Input::PenEraser
+            } else if held.started.elapsed() >= LONG_PRESS_TIME {
+                Input::Touch(Gesture::LongPress)
+            } else {
+                Input::Touch(Gesture::Tap)
+            };
+            game.on_pointer_moved(&point)?;
+            match bindings.command(&input) {
+                Some(command) => run_command(game, window, event_loop, command),
+                None => Ok(()),
+            }
+        }
+        TouchPhase::Cancelled if is_held => {
+            *held_touch = None;
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// winit reports pens as touches, without telling which end is down. On
+/// Windows the touch id is the pointer id, which can be asked about it.
+#[cfg(windows)]
+fn is_pen_eraser(touch: &Touch) -> bool {
+    interop::is_pen_eraser(touch.id as u32)
+}
+
+#[cfg(not(windows))]
+fn is_pen_eraser(_touch: &Touch) -> bool {
+    false
+}
+
+/// Handles the keys that are not used for replays, through the bindings.
+/// Keys are named after their character, or else their winit name, such as
+/// `arrowleft` or `f2`.
+fn on_key<UI: WindowUI>(
+    game: &mut Minesweeper<UI>,
+    window: &Window,
+    event_loop: &EventLoopWindowTarget<()>,
+    bindings: &Bindings,
+    key: &Key,
+    modifiers: ModifiersState,
+) -> Result<(), UI::Error> {
+    let name = match key {
+        Key::Character(text) => text.to_string(),
+        Key::Named(named) => format!("{:?}", named),
+        _ => return Ok(()),
+    };
+    let input = Input::key(
+        &name,
+        modifiers.control_key(),
+        modifiers.alt_key(),
+        modifiers.shift_key(),
+    );
+    match bindings.command(&input) {
+        Some(command) => run_command(game, window, event_loop, command),
+        None => Ok(()),
+    }
+}
+
+/// Carries out a bound command. Hints are also shown in the title bar, the
+/// only place we can show text.
+fn run_command<UI: WindowUI>(
+    game: &mut Minesweeper<UI>,
+    window: &Window,
+    event_loop: &EventLoopWindowTarget<()>,
+    command: Command,
+) -> Result<(), UI::Error> {
+    if command == Command::Quit {
+        event_loop.exit();
+    }
+    game.execute(command)?;
+    if let Some(hint) = game.last_hint() {
+        window.set_title(&format!(
+            "{} - Hint {}: {}",
+            window_title(game.seed()),
+            game.game().hints_used(),
+            hint
+        ));
+    }
+    Ok(())
+}
+
+/// Handles the playback controls: space pauses, the left and right arrows
+/// seek, the up and down arrows change the speed. Returns whether playback
+/// should stop, which escape does.
+fn on_playback_key<UI: WindowUI>(
+    player: &mut ReplayPlayer,
+    game: &mut Minesweeper<UI>,
+    key: &Key,
+) -> Result<bool, UI::Error> {
+    match key {
+        Key::Named(NamedKey::Space) => player.set_paused(!player.is_paused()),
+        Key::Named(NamedKey::ArrowLeft) => {
+            let time = player.time().checked_sub(SEEK_STEP).unwrap_or_default();
+            player.seek(game, time)?;
+        }
+        Key::Named(NamedKey::ArrowRight) => {
+            let time = player.time() + SEEK_STEP;
+            player.seek(game, time)?;
+        }
+        Key::Named(NamedKey::ArrowUp) => player.set_speed(player.speed() * 2.0),
+        Key::Named(NamedKey::ArrowDown) => player.set_speed(player.speed() / 2.0),
+        Key::Named(NamedKey::Escape) => return Ok(true),
+        _ => (),
+    }
+    Ok(false)
+}
+
+/// Saves the game if it is still worth resuming, and otherwise removes the
+/// save from an earlier run so that it does not come back.
+fn save_game<R: BoardRenderer>(game: &Minesweeper<R>, path: &str) -> io::Result<()> {
+    if game.game().are_mines_generated() && !game.game().is_game_over() {
+        return game.save_to_file(path);
+    }
+    match std::fs::remove_file(path) {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+fn load_bindings(path: &str) -> Bindings {
+    Bindings::load_from_file(path).unwrap_or_else(|error| {
+        eprintln!("Ignoring the bindings: {}", error);
+        Bindings::default()
+    })
+}
+
+fn window_title(seed: u64) -> String {
+    format!("Minesweeper - Seed {}", seed)
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("{}", error);
+        std::process::exit(1);
     }
 }
@@ -1,25 +1,20 @@
-use crate::minesweeper::IndexHelper;
+use crate::comp_assets::to_ui_color;
 use crate::numerics::FromVector2;
 use bindings::Windows::{
     Foundation::Numerics::{Vector2, Vector3},
     Graphics::SizeInt32,
-    UI::{
-        Colors,
-        Composition::{Compositor, ContainerVisual, SpriteVisual},
-    },
+    UI::Composition::{Compositor, ContainerVisual, SpriteVisual},
 };
-
-#[derive(Copy, Clone, PartialEq, Debug)]
-pub struct TileCoordinate {
-    pub x: i32,
-    pub y: i32,
-}
+use engine::palette;
+use engine::{IndexHelper, TileCoordinate};
 
 pub struct VisualGrid {
     compositor: Compositor,
     root: ContainerVisual,
 
     tiles: Vec<SpriteVisual>,
+    overlay: ContainerVisual,
+    overlay_tiles: Vec<SpriteVisual>,
     selection_visual: SpriteVisual,
     index_helper: IndexHelper,
 
@@ -40,9 +35,13 @@ impl VisualGrid {
     ) -> windows::Result<Self> {
         let compositor = compositor.clone();
         let root = compositor.CreateContainerVisual()?;
+        let overlay = compositor.CreateContainerVisual()?;
+        overlay.SetRelativeSizeAdjustment(Vector2::new(1.0, 1.0))?;
+        overlay.SetIsVisible(false)?;
 
         let selection_visual = compositor.CreateSpriteVisual()?;
-        let color_brush = compositor.CreateColorBrushWithColor(Colors::Red()?)?;
+        let color_brush =
+            compositor.CreateColorBrushWithColor(to_ui_color(palette::SELECTION_COLOR))?;
         let nine_grid_brush = compositor.CreateNineGridBrush()?;
         nine_grid_brush.SetInsetsWithValues(margin.X, margin.Y, margin.X, margin.Y)?;
         nine_grid_brush.SetIsCenterHollow(true)?;
@@ -57,6 +56,8 @@ impl VisualGrid {
             root,
 
             tiles: Vec::new(),
+            overlay,
+            overlay_tiles: Vec::new(),
             selection_visual,
             index_helper: IndexHelper::new(grid_size_in_tiles.Width, grid_size_in_tiles.Height),
 
@@ -77,6 +78,10 @@ impl VisualGrid {
         let children = self.root.Children()?;
         children.RemoveAll()?;
         self.tiles.clear();
+        let overlay_children = self.overlay.Children()?;
+        overlay_children.RemoveAll()?;
+        self.overlay_tiles.clear();
+        self.overlay.SetIsVisible(false)?;
 
         self.index_helper = IndexHelper::new(grid_size_in_tiles.Width, grid_size_in_tiles.Height);
 
@@ -97,16 +102,24 @@ impl VisualGrid {
                 let visual = self.compositor.CreateSpriteVisual()?;
                 visual.SetSize(&self.tile_size)?;
                 visual.SetCenterPoint(Vector3::from_vector2(&self.tile_size / 2.0, 0.0))?;
-                visual.SetOffset(Vector3::from_vector2(
+                let offset = Vector3::from_vector2(
                     (&self.margin / 2.0)
                         + ((&self.tile_size + &self.margin) * Vector2::new(x as f32, y as f32)),
                     0.0,
-                ))?;
+                );
+                visual.SetOffset(&offset)?;
 
                 children.InsertAtTop(&visual)?;
                 self.tiles.push(visual);
+
+                let overlay_visual = self.compositor.CreateSpriteVisual()?;
+                overlay_visual.SetSize(&self.tile_size)?;
+                overlay_visual.SetOffset(offset)?;
+                overlay_children.InsertAtTop(&overlay_visual)?;
+                self.overlay_tiles.push(overlay_visual);
             }
         }
+        children.InsertAtTop(&self.overlay)?;
 
         Ok(())
     }
@@ -115,6 +128,23 @@ impl VisualGrid {
         self.tiles.iter()
     }
 
+    /// Returns the visual that sits on top of the tile at the same index.
+    /// Overlay visuals are only shown while the overlay is visible.
+    pub fn get_overlay_tile(&self, index: usize) -> Option<&SpriteVisual> {
+        self.overlay_tiles.get(index)
+    }
+
+    /// Moves the overlay back above every tile.
+    pub fn raise_overlay(&self) -> windows::Result<()> {
+        let children = self.root.Children()?;
+        children.Remove(&self.overlay)?;
+        children.InsertAtTop(&self.overlay)
+    }
+
+    pub fn set_overlay_visible(&self, is_visible: bool) -> windows::Result<()> {
+        self.overlay.SetIsVisible(is_visible)
+    }
+
     pub fn root(&self) -> &ContainerVisual {
         &self.root
     }
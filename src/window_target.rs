@@ -1,8 +1,12 @@
 use bindings::Windows::Win32::Foundation::HWND;
 use bindings::Windows::Win32::System::WinRT::ICompositorDesktopInterop;
 use bindings::Windows::UI::Composition::{Compositor, Desktop::DesktopWindowTarget};
-use raw_window_handle::HasRawWindowHandle;
-use windows::Interface;
+use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+use windows::{Interface, HRESULT};
+
+/// Returned for windows that are not Win32 windows, so that the caller can
+/// fall back to another renderer.
+const E_NOTIMPL: HRESULT = HRESULT(0x8000_4001);
 
 pub trait CompositionDesktopWindowTargetSource {
     fn create_window_target(
@@ -14,7 +18,7 @@ pub trait CompositionDesktopWindowTargetSource {
 
 impl<T> CompositionDesktopWindowTargetSource for T
 where
-    T: HasRawWindowHandle,
+    T: HasWindowHandle,
 {
     fn create_window_target(
         &self,
@@ -22,16 +26,16 @@ where
         is_topmost: bool,
     ) -> windows::Result<DesktopWindowTarget> {
         // Get the window handle
-        let window_handle = self.raw_window_handle();
-        let window_handle = match window_handle {
-            raw_window_handle::RawWindowHandle::Windows(window_handle) => window_handle.hwnd,
-            _ => panic!("Unsupported platform!"),
+        let window_handle = self
+            .window_handle()
+            .map_err(|error| windows::Error::new(E_NOTIMPL, &error.to_string()))?;
+        let window_handle = match window_handle.as_raw() {
+            RawWindowHandle::Win32(window_handle) => window_handle.hwnd.get(),
+            _ => return Err(windows::Error::new(E_NOTIMPL, "Unsupported platform!")),
         };
 
         let compositor_desktop: ICompositorDesktopInterop = compositor.cast()?;
 
-        unsafe {
-            compositor_desktop.CreateDesktopWindowTarget(HWND(window_handle as isize), is_topmost)
-        }
+        unsafe { compositor_desktop.CreateDesktopWindowTarget(HWND(window_handle), is_topmost) }
     }
 }
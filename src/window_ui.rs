@@ -0,0 +1,12 @@
+use engine::BoardRenderer;
+
+/// A board renderer that lives in a winit window. Some renderers are drawn by
+/// the system and others have to be asked for every frame.
+pub trait WindowUI: BoardRenderer {
+    /// Draws the board into the window, in response to a redraw request.
+    fn redraw(&mut self) -> Result<(), Self::Error>;
+
+    /// Whether the window should keep redrawing without any input, for
+    /// example while an animation plays.
+    fn needs_frames(&self) -> bool;
+}
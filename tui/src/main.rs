@@ -0,0 +1,352 @@
+mod terminal_ui;
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind,
+    },
+    execute, queue,
+    style::Print,
+    terminal::{
+        self, disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
+};
+use engine::args::{config_from_args, parse_arg};
+use engine::{
+    Bindings, BoardRenderer, Command, GameStatus, Input, MineState, Minesweeper, Point,
+    PointerButton, Size, TileCoordinate,
+};
+use std::error::Error;
+use std::io::{self, Write};
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::time::Duration;
+use terminal_ui::{TerminalUI, BOARD_TOP};
+
+/// How often the mine animation and the timer are brought up to date while
+/// no input arrives.
+const FRAME_TIME: Duration = Duration::from_millis(50);
+
+/// Where the input bindings are read from, unless `--bindings` says
+/// otherwise. The window reads the same file.
+const DEFAULT_BINDINGS_FILE: &str = "minesweeper.bindings";
+
+/// Puts the terminal back the way we found it, even if we panic.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new() -> io::Result<Self> {
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture, Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(
+            io::stdout(),
+            Show,
+            DisableMouseCapture,
+            LeaveAlternateScreen
+        );
+        let _ = disable_raw_mode();
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let config = config_from_args()?;
+    let seed = parse_arg("--seed")?;
+    let bindings = match parse_arg::<String>("--bindings")? {
+        Some(path) => load_bindings(&path),
+        None if Path::new(DEFAULT_BINDINGS_FILE).exists() => load_bindings(DEFAULT_BINDINGS_FILE),
+        None => Bindings::default(),
+    };
+    let help = help(&bindings);
+
+    let _guard = TerminalGuard::new()?;
+    let (columns, rows) = terminal::size()?;
+    let mut game = Minesweeper::new(
+        TerminalUI::new(),
+        &terminal_size(columns, rows),
+        config,
+        seed,
+    )
+    .unwrap();
+
+    let mut cursor = TileCoordinate { x: 0, y: 0 };
+    game.select_tile(cursor).unwrap();
+    let mut message = String::new();
+    let mut stdout = io::stdout();
+    let mut drawn_size = (0, 0);
+    let mut drawn_seconds = 0;
+    let mut needs_redraw = true;
+    loop {
+        // Anything left over from a larger board has to go.
+        let board_size = (game.game().width(), game.game().height());
+        if board_size != drawn_size {
+            queue!(stdout, Clear(ClearType::All))?;
+            drawn_size = board_size;
+        }
+        let seconds = game.elapsed().as_secs();
+        if needs_redraw || seconds != drawn_seconds || game.ui().is_animation_playing() {
+            draw(&mut stdout, &game, cursor, &message, &help)?;
+            drawn_seconds = seconds;
+            needs_redraw = false;
+        }
+        if !event::poll(FRAME_TIME)? {
+            continue;
+        }
+        needs_redraw = true;
+
+        match event::read()? {
+            Event::Key(key) if is_interrupt(&key) => return Ok(()),
+            Event::Key(key) => match on_key(&mut game, &mut cursor, &bindings, &key) {
+                ControlFlow::Continue(key_message) => message = key_message,
+                ControlFlow::Break(()) => return Ok(()),
+            },
+            Event::Mouse(mouse) => match on_mouse(&mut game, &mut cursor, &bindings, &mouse) {
+                ControlFlow::Continue(Some(mouse_message)) => message = mouse_message,
+                ControlFlow::Continue(None) => (),
+                ControlFlow::Break(()) => return Ok(()),
+            },
+            Event::Resize(columns, rows) => {
+                execute!(stdout, Clear(ClearType::All))?;
+                game.on_parent_size_changed(&terminal_size(columns, rows))
+                    .unwrap();
+            }
+        }
+    }
+}
+
+/// Raw mode keeps Ctrl+C from stopping us, so it always quits, whatever the
+/// bindings say.
+fn is_interrupt(key: &KeyEvent) -> bool {
+    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+/// Handles a key press through the bindings and returns the message to show
+/// under the board, or breaks if the key quits. Keys are named like the
+/// window names them, after their character or else their winit name, such
+/// as `arrowleft` or `f2`.
+fn on_key(
+    game: &mut Minesweeper<TerminalUI>,
+    cursor: &mut TileCoordinate,
+    bindings: &Bindings,
+    key: &KeyEvent,
+) -> ControlFlow<(), String> {
+    let name = match key.code {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(character) => character.to_string(),
+        KeyCode::Left => "arrowleft".to_string(),
+        KeyCode::Right => "arrowright".to_string(),
+        KeyCode::Up => "arrowup".to_string(),
+        KeyCode::Down => "arrowdown".to_string(),
+        KeyCode::Enter => "enter".to_string(),
+        KeyCode::Esc => "escape".to_string(),
+        KeyCode::Backspace => "backspace".to_string(),
+        KeyCode::Tab => "tab".to_string(),
+        KeyCode::Delete => "delete".to_string(),
+        KeyCode::Insert => "insert".to_string(),
+        KeyCode::Home => "home".to_string(),
+        KeyCode::End => "end".to_string(),
+        KeyCode::PageUp => "pageup".to_string(),
+        KeyCode::PageDown => "pagedown".to_string(),
+        KeyCode::F(number) => format!("f{}", number),
+        _ => return ControlFlow::Continue(String::new()),
+    };
+    let input = Input::key(
+        &name,
+        key.modifiers.contains(KeyModifiers::CONTROL),
+        key.modifiers.contains(KeyModifiers::ALT),
+        key.modifiers.contains(KeyModifiers::SHIFT),
+    );
+    match bindings.command(&input) {
+        Some(command) => run_command(game, cursor, command),
+        None => ControlFlow::Continue(String::new()),
+    }
+}
+
+/// Handles a mouse event through the bindings. A button bound to `chord`
+/// shows what the chord would reveal while it is held. Returns the message
+/// to show under the board if a command was carried out, or breaks if the
+/// button quits.
+fn on_mouse(
+    game: &mut Minesweeper<TerminalUI>,
+    cursor: &mut TileCoordinate,
+    bindings: &Bindings,
+    mouse: &MouseEvent,
+) -> ControlFlow<(), Option<String>> {
+    let point = Point::new(mouse.column as f32 + 0.5, mouse.row as f32 + 0.5);
+    if let Ok(Some(tile)) = game.ui().hit_test(&point) {
+        *cursor = tile;
+    }
+    game.on_pointer_moved(&point).unwrap();
+
+    let (button, is_pressed) = match mouse.kind {
+        MouseEventKind::Down(button) => (button, true),
+        MouseEventKind::Up(button) => (button, false),
+        _ => return ControlFlow::Continue(None),
+    };
+    let button = match button {
+        MouseButton::Left => PointerButton::Left,
+        MouseButton::Right => PointerButton::Right,
+        MouseButton::Middle => PointerButton::Middle,
+    };
+    let message = match (bindings.command(&Input::Mouse(button)), is_pressed) {
+        (Some(Command::Chord), true) => {
+            game.on_chord_pressed().unwrap();
+            None
+        }
+        (Some(Command::Chord), false) => {
+            game.on_chord_released().unwrap();
+            None
+        }
+        (Some(command), true) => Some(run_command(game, cursor, command)?),
+        _ => return ControlFlow::Continue(None),
+    };
+    game.on_pointer_moved(&point).unwrap();
+    ControlFlow::Continue(message)
+}
+
+/// Carries out a bound command and returns the message to show under the
+/// board, or breaks for `quit`. The cursor follows the tile the game acts
+/// on, which the keyboard moves and hints change.
+fn run_command(
+    game: &mut Minesweeper<TerminalUI>,
+    cursor: &mut TileCoordinate,
+    command: Command,
+) -> ControlFlow<(), String> {
+    if command == Command::Quit {
+        return ControlFlow::Break(());
+    }
+    game.execute(command).unwrap();
+    match game.ui().current_selected_tile().or(game.current_tile()) {
+        Some(tile) => *cursor = tile,
+        None => {
+            // A new game forgets the selection, but the cursor stays where
+            // it was, as far as the new board allows.
+            cursor.x = cursor.x.clamp(0, game.game().width() - 1);
+            cursor.y = cursor.y.clamp(0, game.game().height() - 1);
+            game.select_tile(*cursor).unwrap();
+        }
+    }
+    ControlFlow::Continue(match game.last_hint() {
+        Some(hint) => format!("Hint {}: {}", game.game().hints_used(), hint),
+        None => String::new(),
+    })
+}
+
+/// Lists the bound keys by command, in the order they were bound, such as
+/// `f/x mark`. Mouse buttons are left out, as clicking explains itself.
+fn help(bindings: &Bindings) -> String {
+    let mut commands: Vec<(Command, Vec<&str>)> = Vec::new();
+    for (input, command) in bindings.iter() {
+        if let Input::Key(key) = input {
+            match commands.iter_mut().find(|(bound, _)| bound == command) {
+                Some((_, keys)) => keys.push(key),
+                None => commands.push((*command, vec![key])),
+            }
+        }
+    }
+    commands
+        .iter()
+        .map(|(command, keys)| format!("{} {}", keys.join("/"), command))
+        .collect::<Vec<_>>()
+        .join("  ")
+}
+
+fn draw<W: Write>(
+    out: &mut W,
+    game: &Minesweeper<TerminalUI>,
+    cursor: TileCoordinate,
+    message: &str,
+    help: &str,
+) -> io::Result<()> {
+    let board = game.game();
+    let flags = board
+        .mine_states()
+        .iter()
+        .filter(|state| **state == MineState::Flag)
+        .count() as i32;
+    let status = match board.status() {
+        GameStatus::Playing => "",
+        GameStatus::Won => " - You won! Click or press space to play again.",
+        GameStatus::Lost(_) => " - Boom! Click or press space to play again.",
+    };
+    queue!(
+        out,
+        MoveTo(0, 0),
+        Clear(ClearType::CurrentLine),
+        Print(format!(
+            "{} - Seed {} - Mines {} - Time {}{}",
+            game.difficulty(),
+            game.seed(),
+            board.num_mines() - flags,
+            game.elapsed().as_secs(),
+            status
+        ))
+    )?;
+    game.ui().draw(out, Some(cursor))?;
+
+    let below_board = BOARD_TOP + board.height() as u16 + 1;
+    queue!(
+        out,
+        MoveTo(0, below_board),
+        Clear(ClearType::CurrentLine),
+        Print(message),
+        MoveTo(0, below_board + 1),
+        Clear(ClearType::CurrentLine),
+        Print(help),
+        Clear(ClearType::FromCursorDown)
+    )?;
+    out.flush()
+}
+
+fn load_bindings(path: &str) -> Bindings {
+    Bindings::load_from_file(path).unwrap_or_else(|error| {
+        eprintln!("Ignoring the bindings: {}", error);
+        Bindings::default()
+    })
+}
+
+fn terminal_size(columns: u16, rows: u16) -> Size {
+    Size::new(columns as f32, rows as f32)
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn help_lists_the_bound_keys_by_command() {
+        let mut bindings = Bindings::empty();
+        let key = |name| Input::key(name, false, false, false);
+        bindings.bind(key("f"), Some(Command::Mark));
+        bindings.bind(Input::Mouse(PointerButton::Right), Some(Command::Mark));
+        bindings.bind(Input::key("z", true, false, false), Some(Command::Undo));
+        bindings.bind(key("x"), Some(Command::Mark));
+        bindings.bind(key("escape"), Some(Command::Quit));
+
+        assert_eq!(help(&bindings), "f/x mark  ctrl+z undo  escape quit");
+    }
+
+    #[test]
+    fn rebound_keys_change_the_help() {
+        let mut bindings = Bindings::default();
+        assert!(help(&bindings).contains("  q/escape quit"));
+
+        bindings.bind(Input::key("q", false, false, false), None);
+        bindings.bind(Input::key("x", false, false, false), Some(Command::Quit));
+        assert!(help(&bindings).contains("  escape/x quit"));
+    }
+}
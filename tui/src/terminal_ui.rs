@@ -0,0 +1,294 @@
+use crossterm::{
+    cursor::MoveTo,
+    queue,
+    style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+};
+use engine::{palette, ring_delays, BoardRenderer, MineState, Point, Size, TileCoordinate};
+use raster::{BoardScene, TileView};
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// The terminal row of the top of the board. The rows above it are left for
+/// the status line.
+pub const BOARD_TOP: u16 = 2;
+/// The terminal column of the left edge of the board.
+pub const BOARD_LEFT: u16 = 2;
+/// Every tile is this many columns wide and one row tall, which looks
+/// roughly square in most terminal fonts.
+const TILE_WIDTH: u16 = 3;
+
+/// The delay between two rings of the mine animation, as in the composition
+/// front-end.
+const ANIMATION_DELAY_STEP: Duration = Duration::from_millis(100);
+/// The delay between two rings of the win ripple, and how long each tile
+/// stays lit.
+const RIPPLE_DELAY_STEP: Duration = Duration::from_millis(50);
+const RIPPLE_DURATION: Duration = Duration::from_millis(300);
+
+/// Blends the probability tint over `color` the way the composition overlay
+/// does.
+fn tint(color: palette::Color, probability: f64) -> palette::Color {
+    let tint = palette::probability_color(probability);
+    let alpha = tint.a as f64 / 255.0;
+    let blend =
+        |base: u8, overlay: u8| (base as f64 * (1.0 - alpha) + overlay as f64 * alpha) as u8;
+    palette::Color::rgb(
+        blend(color.r, tint.r),
+        blend(color.g, tint.g),
+        blend(color.b, tint.b),
+    )
+}
+
+/// Picks black or white text, whichever is easier to read on `background`.
+fn text_color(background: palette::Color) -> Color {
+    let luminance =
+        0.299 * background.r as f64 + 0.587 * background.g as f64 + 0.114 * background.b as f64;
+    if luminance < 128.0 {
+        Color::White
+    } else {
+        Color::Black
+    }
+}
+
+fn to_color(color: palette::Color) -> Color {
+    Color::Rgb {
+        r: color.r,
+        g: color.g,
+        b: color.b,
+    }
+}
+
+/// Keeps the board in memory as a `BoardScene`, like the software renderer
+/// of the window, and draws it into a terminal on request. Points are
+/// terminal cells, so the point (10.5, 3.5) is the middle of the cell in
+/// column 10 and row 3.
+pub struct TerminalUI {
+    scene: BoardScene,
+
+    /// Each animated mine with the delay after which it is shown.
+    animated_mines: Vec<(usize, Duration)>,
+    /// Each tile of the win ripple with the delay after which it lights up.
+    rippled_tiles: Vec<(usize, Duration)>,
+    animation_started: Option<Instant>,
+}
+
+impl TerminalUI {
+    pub fn new() -> Self {
+        Self {
+            scene: BoardScene::new(0, 0),
+
+            animated_mines: Vec::new(),
+            rippled_tiles: Vec::new(),
+            animation_started: None,
+        }
+    }
+
+    /// Draws every tile. `cursor` is drawn with brackets around it, even on
+    /// revealed tiles, which the pointer never selects.
+    pub fn draw<W: Write>(&self, out: &mut W, cursor: Option<TileCoordinate>) -> io::Result<()> {
+        let elapsed = self
+            .animation_started
+            .map(|started| started.elapsed())
+            .unwrap_or_default();
+        let mut shown_mines = vec![false; self.scene.tiles.len()];
+        for (index, delay) in &self.animated_mines {
+            if *delay <= elapsed {
+                shown_mines[*index] = true;
+            }
+        }
+        let mut lit_tiles = vec![false; self.scene.tiles.len()];
+        for (index, delay) in &self.rippled_tiles {
+            if *delay <= elapsed && elapsed < *delay + RIPPLE_DURATION {
+                lit_tiles[*index] = true;
+            }
+        }
+
+        let index_helper = self.scene.index_helper();
+        for y in 0..index_helper.height() {
+            queue!(out, MoveTo(BOARD_LEFT, BOARD_TOP + y as u16))?;
+            for x in 0..index_helper.width() {
+                let index = index_helper.compute_index(x, y);
+                let view = if shown_mines[index] {
+                    TileView::Mine
+                } else {
+                    self.scene.tiles[index]
+                };
+                let (mut background, glyph) = match view {
+                    TileView::Covered(state) => (
+                        palette::covered_color(state),
+                        match state {
+                            MineState::Flag => 'F',
+                            MineState::Question => '?',
+                            _ => ' ',
+                        },
+                    ),
+                    TileView::Revealed(0) => (palette::mine_count_color(0), ' '),
+                    TileView::Revealed(count) => (
+                        palette::mine_count_color(count),
+                        std::char::from_digit(count as u32, 10).unwrap_or(' '),
+                    ),
+                    TileView::Mine => (palette::MINE_COLOR, '*'),
+                };
+                if lit_tiles[index] {
+                    background = palette::WIN_COLOR;
+                } else if let TileView::Covered(_) = view {
+                    if let Some(Some(probability)) = self
+                        .scene
+                        .probabilities
+                        .as_ref()
+                        .map(|values| values[index])
+                    {
+                        background = tint(background, probability);
+                    }
+                }
+
+                let (left, right) = if cursor == Some(TileCoordinate { x, y }) {
+                    ('[', ']')
+                } else {
+                    (' ', ' ')
+                };
+                queue!(
+                    out,
+                    SetBackgroundColor(to_color(background)),
+                    SetForegroundColor(text_color(background)),
+                    Print(format!("{}{}{}", left, glyph, right))
+                )?;
+            }
+            queue!(out, ResetColor)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for TerminalUI {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BoardRenderer for TerminalUI {
+    type Error = Infallible;
+
+    fn reset(&mut self, width: i32, height: i32) -> Result<(), Self::Error> {
+        self.scene = BoardScene::new(width, height);
+        self.stop_animations()
+    }
+
+    fn resize(&mut self, _new_size: &Size) -> Result<(), Self::Error> {
+        // The board is drawn at a fixed position, so a terminal that is too
+        // small simply cuts it off.
+        Ok(())
+    }
+
+    fn hit_test(&self, point: &Point) -> Result<Option<TileCoordinate>, Self::Error> {
+        let column = point.x - BOARD_LEFT as f32;
+        let row = point.y - BOARD_TOP as f32;
+        if column < 0.0 || row < 0.0 {
+            return Ok(None);
+        }
+
+        let x = column as i32 / TILE_WIDTH as i32;
+        let y = row as i32;
+        if self.scene.index_helper().is_in_bounds(x, y) {
+            Ok(Some(TileCoordinate { x, y }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn select_tile(&mut self, tile_coordinate: Option<TileCoordinate>) -> Result<(), Self::Error> {
+        self.scene.selection = tile_coordinate;
+        Ok(())
+    }
+
+    fn current_selected_tile(&self) -> Option<TileCoordinate> {
+        self.scene.selection
+    }
+
+    fn update_tile_with_state(
+        &mut self,
+        tile_coordinate: &TileCoordinate,
+        mine_state: MineState,
+    ) -> Result<(), Self::Error> {
+        self.scene
+            .set_tile(tile_coordinate, TileView::Covered(mine_state));
+        Ok(())
+    }
+
+    fn update_tile_as_mine(&mut self, tile_coordinate: &TileCoordinate) -> Result<(), Self::Error> {
+        self.scene.set_tile(tile_coordinate, TileView::Mine);
+        Ok(())
+    }
+
+    fn update_tile_as_pressed(
+        &mut self,
+        tile_coordinate: &TileCoordinate,
+    ) -> Result<(), Self::Error> {
+        self.scene.set_tile(tile_coordinate, TileView::Revealed(0));
+        Ok(())
+    }
+
+    fn update_tile_with_mine_count(
+        &mut self,
+        tile_coordinate: &TileCoordinate,
+        num_mines: i32,
+    ) -> Result<(), Self::Error> {
+        self.scene
+            .set_tile(tile_coordinate, TileView::Revealed(num_mines));
+        Ok(())
+    }
+
+    fn play_mine_animations(
+        &mut self,
+        mine_indices: VecDeque<usize>,
+        mines_per_ring: VecDeque<i32>,
+    ) -> Result<(), Self::Error> {
+        self.animated_mines = ring_delays(mine_indices, mines_per_ring, ANIMATION_DELAY_STEP);
+        self.animation_started = Some(Instant::now());
+
+        Ok(())
+    }
+
+    fn play_win_animation(
+        &mut self,
+        tile_indices: VecDeque<usize>,
+        tiles_per_ring: VecDeque<i32>,
+    ) -> Result<(), Self::Error> {
+        self.rippled_tiles = ring_delays(tile_indices, tiles_per_ring, RIPPLE_DELAY_STEP);
+        self.animation_started = Some(Instant::now());
+        Ok(())
+    }
+
+    fn is_animation_playing(&self) -> bool {
+        let end = |tiles: &[(usize, Duration)], duration: Duration| {
+            tiles
+                .last()
+                .map(|(_, delay)| *delay + duration)
+                .unwrap_or_default()
+        };
+        let end = end(&self.animated_mines, ANIMATION_DELAY_STEP)
+            .max(end(&self.rippled_tiles, RIPPLE_DURATION));
+        self.animation_started
+            .is_some_and(|started| started.elapsed() < end)
+    }
+
+    fn stop_animations(&mut self) -> Result<(), Self::Error> {
+        self.animated_mines.clear();
+        self.rippled_tiles.clear();
+        self.animation_started = None;
+        Ok(())
+    }
+
+    fn show_probabilities(&mut self, probabilities: &[Option<f64>]) -> Result<(), Self::Error> {
+        self.scene.probabilities = Some(probabilities.to_vec());
+        Ok(())
+    }
+
+    fn hide_probabilities(&mut self) -> Result<(), Self::Error> {
+        self.scene.probabilities = None;
+        Ok(())
+    }
+}